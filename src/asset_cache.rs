@@ -1,61 +1,182 @@
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
 
-use crate::coords::block_face::BlockFace;
+use crate::biome::Colormaps;
+use crate::block_model::BlockModels;
+use crate::block_registry::BlockId;
+use crate::coords::block_face::{BlockFace, VisibleFaces};
+use crate::data_file::{DataFileError, read_json};
 use crate::light_data::LightData;
+use crate::render::render_fluid::FluidContext;
+use crate::render::renderer::SpritePlan;
+use crate::texture_animation::{Animation, AnimationFrame, TextureMeta};
+use crate::texture_map::TextureMap;
 use image::RgbaImage;
 
+/// Texture whose resolution sets the default sprite size, one every
+/// resource pack is expected to have
+const REFERENCE_TEXTURE: &str = "stone";
+
+/// A transformed layer of a face, see `FaceLayer`
 #[derive(Hash, Eq, PartialEq)]
 pub struct BlockPartKey {
     pub face: BlockFace,
-    pub name: String,
+    pub texture: String,
+    pub uv: [u8; 4],
+    pub turns: u8,
+}
+
+/// The region of a texture a layer draws, see `layer_texture`
+#[derive(Hash, Eq, PartialEq)]
+pub struct LayerKey {
+    pub texture: String,
+    pub uv: [u8; 4],
+    pub turns: u8,
 }
 
 #[derive(Hash, Eq, PartialEq)]
 pub struct BlockSpriteKey {
     pub light: LightData,
-    // pub light: u8,
-    pub name: String,
+    pub block: BlockId,
+    pub faces: VisibleFaces,
+    /// Biome colour as RGB, for tinted blocks
+    pub tint: Option<[u8; 3]>,
+    pub fluid: FluidContext,
 }
 
 pub struct AssetCache {
-    pub texture_cache: RwLock<HashMap<String, RgbaImage>>,
+    /// Loaded textures, none for those missing
+    pub texture_cache: RwLock<HashMap<String, Option<RgbaImage>>>,
+    pub layer_cache: RwLock<HashMap<LayerKey, RgbaImage>>,
     pub block_part_cache: RwLock<HashMap<BlockPartKey, RgbaImage>>,
     pub block_sprite_cache: RwLock<HashMap<BlockSpriteKey, RgbaImage>>,
+    /// Textures of each block state, none for blocks that are not drawn
+    pub block_plan_cache: RwLock<HashMap<BlockId, Option<Arc<SpritePlan>>>>,
+    pub colormaps: Colormaps,
+    pub models: BlockModels,
+    pub texture_map: TextureMap,
     pub assets_path: String,
+    /// Frame drawn of animated textures
+    pub animation_frame: AnimationFrame,
+    /// Width and height of a block's sprite in pixels, a multiple of 8
+    pub sprite_size: u32,
 }
 
 impl AssetCache {
-    pub fn new(assets_path: &str) -> Self {
-        Self {
+    /// Sprites are `sprite_size` pixels, or sized to suit the textures
+    pub fn new(
+        assets_path: &str,
+        texture_map: TextureMap,
+        animation_frame: AnimationFrame,
+        sprite_size: Option<u32>,
+    ) -> Self {
+        let mut cache = Self {
             texture_cache: RwLock::new(HashMap::new()),
+            layer_cache: RwLock::new(HashMap::new()),
             block_part_cache: RwLock::new(HashMap::new()),
             block_sprite_cache: RwLock::new(HashMap::new()),
+            block_plan_cache: RwLock::new(HashMap::new()),
+            colormaps: Colormaps::load(assets_path),
+            models: BlockModels::new(assets_path),
+            texture_map,
             assets_path: assets_path.to_string(),
-        }
+            animation_frame,
+            sprite_size: 0,
+        };
+        cache.sprite_size = sprite_size.unwrap_or_else(|| cache.texture_sprite_size());
+        cache
     }
 
+    /// Sprite size showing every pixel of the textures, judged by the
+    /// resolution of `REFERENCE_TEXTURE`: 24 pixels for 16 pixel textures,
+    /// and one and a half times the resolution of others, rounded up to a
+    /// multiple of 8
+    fn texture_sprite_size(&self) -> u32 {
+        let resolution = if self.has_texture(REFERENCE_TEXTURE) {
+            self.load_texture(REFERENCE_TEXTURE)
+                .map_or(16, |texture| texture.width())
+        } else {
+            16
+        };
+        (resolution * 3 / 2).div_ceil(8).max(1) * 8
+    }
+
+    /// A texture, reported missing only the first time it is asked for
     pub fn load_texture(&self, texture_name: &str) -> Option<RgbaImage> {
         {
             let cache = self.texture_cache.read().unwrap();
             if let Some(cached) = cache.get(texture_name) {
-                return Some(cached.clone());
+                return cached.clone();
             }
         }
 
-        let path = format!(
-            "{}/minecraft/textures/block/{}.png",
-            self.assets_path, texture_name
-        );
-
-        if let Ok(img) = image::open(&path) {
+        let path = self.texture_path(texture_name);
+        let texture = image::open(&path).ok().map(|img| {
             let rgba = img.to_rgba8();
-            let mut cache = self.texture_cache.write().unwrap();
-            cache.insert(texture_name.to_string(), rgba.clone());
-            Some(rgba)
-        } else {
-            println!("texture {} not found", texture_name);
-            None
+            // Animated textures hold their frames one after the other
+            match self.texture_animation(&path) {
+                Some(animation) => animation.frame(&rgba, self.animation_frame),
+                None => rgba,
+            }
+        });
+
+        // Another thread may have loaded it meanwhile
+        let mut cache = self.texture_cache.write().unwrap();
+        cache
+            .entry(texture_name.to_string())
+            .or_insert_with(|| {
+                if texture.is_none() {
+                    println!("texture {} not found", texture_name);
+                }
+                texture
+            })
+            .clone()
+    }
+
+    /// Animation of the texture at `path`, from its `.png.mcmeta` file
+    fn texture_animation(&self, path: &str) -> Option<Animation> {
+        let meta_path = format!("{}.mcmeta", path);
+        match read_json::<TextureMeta>(Path::new(&meta_path)) {
+            Ok(meta) => meta.animation,
+            Err(DataFileError::Io(_, err)) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                println!("{}", err);
+                None
+            }
         }
     }
+
+    /// Texture for a face of a block without a model, the first of the
+    /// `TextureMap` candidates that exists
+    pub fn find_texture(&self, name: &str, face: &BlockFace) -> String {
+        let mut candidates = self.texture_map.candidates(name, face);
+        match candidates.iter().position(|c| self.has_texture(c)) {
+            Some(found) => candidates.swap_remove(found),
+            None => candidates.swap_remove(0),
+        }
+    }
+
+    /// Whether a texture is loaded or can be, without reporting it missing
+    pub fn has_texture(&self, texture_name: &str) -> bool {
+        matches!(
+            self.texture_cache.read().unwrap().get(texture_name),
+            Some(Some(_))
+        ) || Path::new(&self.texture_path(texture_name)).is_file()
+    }
+
+    /// Path of a block texture, in the `minecraft` namespace unless the
+    /// name has one like `mymod:ruby_ore`. Names with a directory, like
+    /// `entity/chest/normal`, are textures of other kinds
+    fn texture_path(&self, texture_name: &str) -> String {
+        let (namespace, name) = texture_name
+            .split_once(':')
+            .unwrap_or(("minecraft", texture_name));
+        let kind = if name.contains('/') { "" } else { "block/" };
+        format!(
+            "{}/{}/textures/{}{}.png",
+            self.assets_path, namespace, kind, name
+        )
+    }
 }
@@ -0,0 +1,207 @@
+use image::RgbaImage;
+use serde::Deserialize;
+
+/// Biome used where a block has no biome data
+pub const DEFAULT_BIOME: &str = "minecraft:plains";
+
+/// Grass colour of plains, used when `grass.png` is missing
+const FALLBACK_GRASS: u32 = 0x91BD59;
+/// Foliage colour of plains, used when `foliage.png` is missing
+const FALLBACK_FOLIAGE: u32 = 0x77AB2F;
+const DEFAULT_WATER: u32 = 0x3F76E4;
+
+/// Which colour a tinted block takes from its biome. Written as `grass`,
+/// `foliage`, `water` or a fixed `#RRGGBB` colour in block metadata
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub enum TintType {
+    Grass,
+    Foliage,
+    Water,
+    /// A colour that does not depend on the biome, like spruce leaves
+    Fixed(u32),
+}
+
+impl TryFrom<String> for TintType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "grass" => Ok(TintType::Grass),
+            "foliage" => Ok(TintType::Foliage),
+            "water" => Ok(TintType::Water),
+            _ => value
+                .strip_prefix('#')
+                .filter(|hex| hex.len() == 6)
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .map(TintType::Fixed)
+                .ok_or_else(|| format!("unknown tint {:?}", value)),
+        }
+    }
+}
+
+/// The vanilla `grass.png` and `foliage.png` colormaps
+pub struct Colormaps {
+    grass: Option<RgbaImage>,
+    foliage: Option<RgbaImage>,
+}
+
+impl Colormaps {
+    pub fn load(assets_path: &str) -> Self {
+        let load = |name: &str| {
+            let path = format!("{}/minecraft/textures/colormap/{}.png", assets_path, name);
+            match image::open(&path) {
+                Ok(img) => Some(img.to_rgba8()),
+                Err(_) => {
+                    println!("colormap {} not found, using plains colours", name);
+                    None
+                }
+            }
+        };
+        Self {
+            grass: load("grass"),
+            foliage: load("foliage"),
+        }
+    }
+
+    /// Colour of a tint in a biome, as RGB
+    pub fn tint_color(&self, tint: TintType, biome: &str) -> [u8; 3] {
+        let biome = biome.strip_prefix("minecraft:").unwrap_or(biome);
+        let color = match tint {
+            TintType::Grass => grass_color(biome).unwrap_or_else(|| {
+                let color = sample(&self.grass, biome).unwrap_or(FALLBACK_GRASS);
+                if biome == "dark_forest" {
+                    // Dark forests darken the colormap colour
+                    ((color & 0xFEFEFE) + 0x28340A) >> 1
+                } else {
+                    color
+                }
+            }),
+            TintType::Foliage => foliage_color(biome)
+                .unwrap_or_else(|| sample(&self.foliage, biome).unwrap_or(FALLBACK_FOLIAGE)),
+            TintType::Water => water_color(biome),
+            TintType::Fixed(color) => color,
+        };
+        [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+    }
+}
+
+/// Look up the colormap pixel for the biome's climate, like the game does
+fn sample(colormap: &Option<RgbaImage>, biome: &str) -> Option<u32> {
+    let colormap = colormap.as_ref()?;
+    let (temperature, downfall) = climate(biome);
+    let temperature = temperature.clamp(0.0, 1.0);
+    let downfall = downfall.clamp(0.0, 1.0) * temperature;
+
+    let x = ((1.0 - temperature) * (colormap.width() - 1) as f32) as u32;
+    let y = ((1.0 - downfall) * (colormap.height() - 1) as f32) as u32;
+    let [r, g, b, _] = colormap.get_pixel(x, y).0;
+    Some(((r as u32) << 16) | ((g as u32) << 8) | b as u32)
+}
+
+/// Biomes with a hardcoded grass colour instead of a colormap lookup
+fn grass_color(biome: &str) -> Option<u32> {
+    match biome {
+        "swamp" | "mangrove_swamp" => Some(0x6A7039),
+        "badlands" | "eroded_badlands" | "wooded_badlands" => Some(0x90814D),
+        "cherry_grove" => Some(0xB6DB61),
+        "pale_garden" => Some(0x778272),
+        _ => None,
+    }
+}
+
+/// Biomes with a hardcoded foliage colour instead of a colormap lookup
+fn foliage_color(biome: &str) -> Option<u32> {
+    match biome {
+        "swamp" => Some(0x6A7039),
+        "mangrove_swamp" => Some(0x8DB127),
+        "badlands" | "eroded_badlands" | "wooded_badlands" => Some(0x9E814D),
+        "cherry_grove" => Some(0xB6DB61),
+        "pale_garden" => Some(0x878D76),
+        _ => None,
+    }
+}
+
+fn water_color(biome: &str) -> u32 {
+    match biome {
+        "swamp" => 0x617B64,
+        "mangrove_swamp" => 0x3A7A6A,
+        "warm_ocean" => 0x43D5EE,
+        "lukewarm_ocean" | "deep_lukewarm_ocean" => 0x45ADF2,
+        "cold_ocean" | "deep_cold_ocean" | "snowy_taiga" | "snowy_beach" => 0x3D57D6,
+        "frozen_ocean" | "deep_frozen_ocean" | "frozen_river" => 0x3938C9,
+        "meadow" => 0x0E4ECF,
+        "cherry_grove" => 0x5DB7EF,
+        "pale_garden" => 0x76889D,
+        _ => DEFAULT_WATER,
+    }
+}
+
+/// Temperature and downfall of the vanilla biomes
+fn climate(biome: &str) -> (f32, f32) {
+    match biome {
+        "desert" | "savanna" | "savanna_plateau" | "windswept_savanna" | "badlands"
+        | "eroded_badlands" | "wooded_badlands" | "nether_wastes" | "soul_sand_valley"
+        | "crimson_forest" | "warped_forest" | "basalt_deltas" => (2.0, 0.0),
+        "plains" | "sunflower_plains" | "beach" | "dripstone_caves" | "deep_dark" => (0.8, 0.4),
+        "swamp" | "mangrove_swamp" => (0.8, 0.9),
+        "forest" | "flower_forest" | "dark_forest" | "pale_garden" => (0.7, 0.8),
+        "birch_forest" | "old_growth_birch_forest" => (0.6, 0.6),
+        "old_growth_pine_taiga" => (0.3, 0.8),
+        "taiga" | "old_growth_spruce_taiga" => (0.25, 0.8),
+        "snowy_taiga" => (-0.5, 0.4),
+        "snowy_plains" | "ice_spikes" | "frozen_river" | "frozen_ocean" => (0.0, 0.5),
+        "windswept_hills" | "windswept_gravelly_hills" | "windswept_forest" | "stony_shore" => {
+            (0.2, 0.3)
+        }
+        "jungle" | "bamboo_jungle" => (0.95, 0.9),
+        "sparse_jungle" => (0.95, 0.8),
+        "meadow" | "cherry_grove" => (0.5, 0.8),
+        "grove" => (-0.2, 0.8),
+        "snowy_slopes" => (-0.3, 0.9),
+        "frozen_peaks" | "jagged_peaks" => (-0.7, 0.9),
+        "stony_peaks" => (1.0, 0.3),
+        "snowy_beach" => (0.05, 0.3),
+        "mushroom_fields" => (0.9, 1.0),
+        // Rivers, oceans, lush caves, the End and anything unknown
+        _ => (0.5, 0.5),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn tint_colors_use_colormap_or_biome_overrides() {
+        // A colormap whose pixels encode their own coordinates
+        let colormap = RgbaImage::from_fn(256, 256, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+        let colormaps = Colormaps {
+            grass: Some(colormap.clone()),
+            foliage: Some(colormap),
+        };
+
+        // Plains: temperature 0.8, downfall 0.4 * 0.8
+        assert_eq!(
+            colormaps.tint_color(TintType::Grass, "minecraft:plains"),
+            [50, 173, 0]
+        );
+        assert_eq!(
+            colormaps.tint_color(TintType::Foliage, "minecraft:swamp"),
+            [0x6A, 0x70, 0x39]
+        );
+        assert_eq!(
+            colormaps.tint_color(TintType::Water, "minecraft:plains"),
+            [0x3F, 0x76, 0xE4]
+        );
+        assert_eq!(
+            Colormaps {
+                grass: None,
+                foliage: None
+            }
+            .tint_color(TintType::Grass, "minecraft:forest"),
+            [0x91, 0xBD, 0x59]
+        );
+    }
+}
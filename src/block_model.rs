@@ -0,0 +1,738 @@
+use std::collections::HashMap;
+use std::io;
+use std::ops::Neg;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::coords::block_face::BlockFace;
+use crate::data_file::{DataFileError, read_json};
+use crate::section::PaletteEntry;
+
+/// Parents followed before giving up, in case models inherit in a circle
+const MAX_PARENTS: usize = 16;
+
+/// Texture variables followed before giving up, like `#all` pointing at `#side`
+const MAX_TEXTURE_REFERENCES: usize = 8;
+
+/// A side of a model or block, named like in the model files
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 6] = [
+        Direction::Down,
+        Direction::Up,
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ];
+
+    fn normal(self) -> [i32; 3] {
+        match self {
+            Direction::Down => [0, -1, 0],
+            Direction::Up => [0, 1, 0],
+            Direction::North => [0, 0, -1],
+            Direction::South => [0, 0, 1],
+            Direction::West => [-1, 0, 0],
+            Direction::East => [1, 0, 0],
+        }
+    }
+
+    /// Directions in which the texture's U and V grow on this face
+    fn texture_axes(self) -> ([i32; 3], [i32; 3]) {
+        match self {
+            Direction::Down => ([1, 0, 0], [0, 0, -1]),
+            Direction::Up => ([1, 0, 0], [0, 0, 1]),
+            Direction::North => ([-1, 0, 0], [0, -1, 0]),
+            Direction::South => ([1, 0, 0], [0, -1, 0]),
+            Direction::West => ([0, 0, 1], [0, -1, 0]),
+            Direction::East => ([0, 0, -1], [0, -1, 0]),
+        }
+    }
+}
+
+impl From<&BlockFace> for Direction {
+    fn from(face: &BlockFace) -> Self {
+        match face {
+            BlockFace::East => Direction::East,
+            BlockFace::South => Direction::South,
+            BlockFace::Top => Direction::Up,
+        }
+    }
+}
+
+/// One model or several to pick from
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Variants {
+    One(Variant),
+    Many(Vec<Variant>),
+}
+
+impl Variants {
+    /// The game picks a random variant by weight, the first keeps renders stable
+    fn first(&self) -> Option<&Variant> {
+        match self {
+            Variants::One(variant) => Some(variant),
+            Variants::Many(variants) => variants.first(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Variant {
+    model: String,
+    /// Rotation around the X axis in degrees, a multiple of 90
+    #[serde(default)]
+    x: i32,
+    /// Rotation around the Y axis in degrees, a multiple of 90
+    #[serde(default)]
+    y: i32,
+    /// Keeps the textures aligned to the world when rotating
+    #[serde(default)]
+    uvlock: bool,
+}
+
+/// Block states a multipart case applies to
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Condition {
+    Or {
+        #[serde(rename = "OR")]
+        any: Vec<Condition>,
+    },
+    And {
+        #[serde(rename = "AND")]
+        all: Vec<Condition>,
+    },
+    /// Property values, with alternatives separated by `|`
+    Properties(HashMap<String, serde_json::Value>),
+}
+
+impl Condition {
+    fn matches(&self, state: &PaletteEntry) -> bool {
+        match self {
+            Condition::Or { any } => any.iter().any(|c| c.matches(state)),
+            Condition::And { all } => all.iter().all(|c| c.matches(state)),
+            Condition::Properties(properties) => properties.iter().all(|(key, value)| {
+                let expected = match value {
+                    serde_json::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                let actual = state.property(key);
+                expected.split('|').any(|value| Some(value) == actual)
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct MultipartCase {
+    when: Option<Condition>,
+    apply: Variants,
+}
+
+/// A file of `blockstates/`, choosing models by block state. Either every
+/// state has one variant, or each case of a multipart adds a model
+#[derive(Deserialize, Debug, Default)]
+struct BlockstateFile {
+    #[serde(default)]
+    variants: HashMap<String, Variants>,
+    #[serde(default)]
+    multipart: Vec<MultipartCase>,
+}
+
+impl BlockstateFile {
+    fn variants_for(&self, state: &PaletteEntry) -> Vec<&Variant> {
+        // Keys are like `axis=x,facing=north`, naming only the properties
+        // that matter, or empty for blocks with a single variant. Of several
+        // matching keys the one naming the most properties wins, then the
+        // lowest in key order, as the map has no order of its own
+        let pairs = |key: &String| key.split(',').filter(|pair| !pair.is_empty()).count();
+        let variant = self
+            .variants
+            .iter()
+            .filter(|(key, _)| {
+                key.split(',').filter(|pair| !pair.is_empty()).all(|pair| {
+                    match pair.split_once('=') {
+                        Some((key, value)) => state.property(key) == Some(value),
+                        None => false,
+                    }
+                })
+            })
+            .max_by(|(a, _), (b, _)| pairs(a).cmp(&pairs(b)).then(b.cmp(a)))
+            .map(|(_, variants)| variants);
+
+        let cases = self.multipart.iter().filter(|case| {
+            case.when
+                .as_ref()
+                .is_none_or(|condition| condition.matches(state))
+        });
+
+        variant
+            .into_iter()
+            .chain(cases.map(|case| &case.apply))
+            .filter_map(Variants::first)
+            .collect()
+    }
+}
+
+/// A face of a model element, with `texture` a variable like `#side`
+/// until the model is resolved
+#[derive(Deserialize, Debug, Clone)]
+pub struct ElementFace {
+    pub texture: String,
+    /// Region of the texture as `[u1, v1, u2, v2]` in 16ths of its size.
+    /// Taken from the element's position when not given
+    pub uv: Option<[f32; 4]>,
+    /// Clockwise rotation of the texture in degrees
+    #[serde(default)]
+    pub rotation: i32,
+    /// Faces with a tint index take the block's biome colour
+    pub tintindex: Option<i32>,
+}
+
+/// Rotation of a model element about one axis through `origin`
+#[derive(Deserialize, Debug, Clone)]
+pub struct ElementRotation {
+    pub axis: String,
+    /// Degrees, a multiple of 22.5 between -45 and 45
+    pub angle: f32,
+}
+
+/// A box of a model, in 16ths of a block
+#[derive(Deserialize, Debug, Clone)]
+pub struct ModelElement {
+    pub from: [f32; 3],
+    pub to: [f32; 3],
+    pub rotation: Option<ElementRotation>,
+    #[serde(default)]
+    pub faces: HashMap<Direction, ElementFace>,
+}
+
+impl ModelElement {
+    /// Whether the element is one of the diagonal planes of a plant, a
+    /// flat box turned 45 degrees about the Y axis
+    pub fn is_cross_plane(&self) -> bool {
+        let flat = self.from[0] == self.to[0] || self.from[2] == self.to[2];
+        flat && self
+            .rotation
+            .as_ref()
+            .is_some_and(|rotation| rotation.axis == "y" && rotation.angle.abs() == 45.0)
+    }
+
+    /// Whether the element fills the whole block
+    pub fn is_full_cube(&self) -> bool {
+        self.from == [0.0; 3] && self.to == [16.0; 3]
+    }
+
+    /// Texture region of a face, the one given or else the region under
+    /// the element like the game chooses
+    pub fn face_uv(&self, face: Direction) -> [f32; 4] {
+        self.faces
+            .get(&face)
+            .and_then(|f| f.uv)
+            .unwrap_or_else(|| default_uv(self.from, self.to, face))
+    }
+}
+
+/// Texture region under a face of a box, in 16ths, the way the game maps
+/// faces without a region of their own
+pub fn default_uv(from: [f32; 3], to: [f32; 3], face: Direction) -> [f32; 4] {
+    let [x1, y1, z1] = from;
+    let [x2, y2, z2] = to;
+    match face {
+        Direction::Down => [x1, 16.0 - z2, x2, 16.0 - z1],
+        Direction::Up => [x1, z1, x2, z2],
+        Direction::North => [16.0 - x2, 16.0 - y2, 16.0 - x1, 16.0 - y1],
+        Direction::South => [x1, 16.0 - y2, x2, 16.0 - y1],
+        Direction::West => [z1, 16.0 - y2, z2, 16.0 - y1],
+        Direction::East => [16.0 - z2, 16.0 - y2, 16.0 - z1, 16.0 - y1],
+    }
+}
+
+/// A file of `models/`. Textures and elements not set are inherited from
+/// the parent
+#[derive(Deserialize, Debug, Default)]
+struct ModelFile {
+    parent: Option<String>,
+    #[serde(default)]
+    textures: HashMap<String, String>,
+    elements: Option<Vec<ModelElement>>,
+}
+
+/// A model with its texture variables resolved, rotated into place
+#[derive(Debug, Clone)]
+pub struct PlacedModel {
+    pub elements: Vec<ModelElement>,
+    /// Quarter turns around the X axis, applied first
+    x_turns: u8,
+    /// Quarter turns around the Y axis
+    y_turns: u8,
+    uvlock: bool,
+}
+
+impl PlacedModel {
+    fn rotate<T: Copy + Neg<Output = T>>(&self, vector: [T; 3]) -> [T; 3] {
+        let mut v = vector;
+        for _ in 0..self.x_turns {
+            v = [v[0], v[2], -v[1]];
+        }
+        for _ in 0..self.y_turns {
+            v = [-v[2], v[1], v[0]];
+        }
+        v
+    }
+
+    /// Corners of an element's box once the model is rotated about the
+    /// centre of the block
+    pub fn rotate_box(&self, from: [f32; 3], to: [f32; 3]) -> ([f32; 3], [f32; 3]) {
+        let a = self.rotate(from.map(|c| c - 8.0));
+        let b = self.rotate(to.map(|c| c - 8.0));
+        (
+            [0, 1, 2].map(|axis| a[axis].min(b[axis]) + 8.0),
+            [0, 1, 2].map(|axis| a[axis].max(b[axis]) + 8.0),
+        )
+    }
+
+    /// The side of the model that ends up facing `side` of the block
+    pub fn model_face(&self, side: Direction) -> Direction {
+        Direction::ALL
+            .into_iter()
+            .find(|face| self.rotate(face.normal()) == side.normal())
+            .expect("rotations map sides onto sides")
+    }
+
+    /// Clockwise quarter turns the rotation gives the textures of a face
+    /// of the model
+    pub fn texture_turns(&self, face: Direction) -> u8 {
+        if self.uvlock {
+            return 0;
+        }
+        let (u, v) = face.texture_axes();
+        let rotated = (self.rotate(u), self.rotate(v));
+        let side = self.side_of(face);
+
+        let (mut u, mut v) = side.texture_axes();
+        for turns in 0..4 {
+            if (u, v) == rotated {
+                return turns;
+            }
+            (u, v) = (v, u.map(|c| -c));
+        }
+        0
+    }
+
+    /// The side of the block a face of the model ends up facing
+    fn side_of(&self, face: Direction) -> Direction {
+        let normal = self.rotate(face.normal());
+        Direction::ALL
+            .into_iter()
+            .find(|side| side.normal() == normal)
+            .expect("rotations map sides onto sides")
+    }
+}
+
+/// Reads the vanilla `blockstates/` and `models/` files of the assets, or
+/// those of other namespaces, to find the models of block states. Files
+/// are read once and kept
+pub struct BlockModels {
+    assets_path: String,
+    blockstates: RwLock<HashMap<String, Option<Arc<BlockstateFile>>>>,
+    model_files: RwLock<HashMap<String, Option<Arc<ModelFile>>>>,
+}
+
+impl BlockModels {
+    pub fn new(assets_path: &str) -> Self {
+        Self {
+            assets_path: assets_path.to_string(),
+            blockstates: RwLock::new(HashMap::new()),
+            model_files: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Models of a block state, empty if the assets have no blockstate file
+    /// for the block. `name` is like `BlockInfo::name`
+    pub fn models_for(&self, name: &str, state: &PaletteEntry) -> Vec<PlacedModel> {
+        let (namespace, path) = split_namespace(name);
+        let Some(blockstate) = self.load(
+            &self.blockstates,
+            name,
+            &format!(
+                "{}/{}/blockstates/{}.json",
+                self.assets_path, namespace, path
+            ),
+        ) else {
+            return Vec::new();
+        };
+
+        blockstate
+            .variants_for(state)
+            .into_iter()
+            .filter_map(|variant| {
+                Some(PlacedModel {
+                    elements: self.resolve_model(&variant.model)?,
+                    x_turns: (variant.x.rem_euclid(360) / 90) as u8,
+                    y_turns: (variant.y.rem_euclid(360) / 90) as u8,
+                    uvlock: variant.uvlock,
+                })
+            })
+            .collect()
+    }
+
+    /// Elements of a model with the texture names filled in, following its
+    /// parents. Faces whose texture variable is not set are left out
+    fn resolve_model(&self, reference: &str) -> Option<Vec<ModelElement>> {
+        let mut textures = HashMap::new();
+        let mut elements = None;
+        let mut next = Some(reference.to_string());
+        for _ in 0..MAX_PARENTS {
+            let Some(reference) = next.take() else {
+                break;
+            };
+            let Some(model) = self.model_file(&reference) else {
+                break;
+            };
+            for (variable, value) in &model.textures {
+                textures
+                    .entry(variable.clone())
+                    .or_insert_with(|| value.clone());
+            }
+            if elements.is_none() {
+                elements = model.elements.clone();
+            }
+            next = model.parent.clone();
+        }
+
+        let mut elements = elements?;
+        for element in &mut elements {
+            element
+                .faces
+                .retain(|_, face| match resolve_texture(&textures, &face.texture) {
+                    Some(texture) => {
+                        face.texture = texture;
+                        true
+                    }
+                    None => false,
+                });
+        }
+        Some(elements)
+    }
+
+    fn model_file(&self, reference: &str) -> Option<Arc<ModelFile>> {
+        let (namespace, path) = split_namespace(reference);
+        // Built in models like `builtin/entity` have no file
+        if namespace == "minecraft" && path.starts_with("builtin/") {
+            return None;
+        }
+        self.load(
+            &self.model_files,
+            &format!("{}:{}", namespace, path),
+            &format!("{}/{}/models/{}.json", self.assets_path, namespace, path),
+        )
+    }
+
+    /// A file from the cache, or else read from `path`. Missing files are
+    /// remembered as such; unreadable ones are reported and skipped
+    fn load<T: DeserializeOwned>(
+        &self,
+        cache: &RwLock<HashMap<String, Option<Arc<T>>>>,
+        key: &str,
+        path: &str,
+    ) -> Option<Arc<T>> {
+        if let Some(cached) = cache.read().unwrap().get(key) {
+            return cached.clone();
+        }
+
+        let file = match read_json(Path::new(path)) {
+            Ok(file) => Some(Arc::new(file)),
+            Err(DataFileError::Io(_, err)) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                println!("{}", err);
+                None
+            }
+        };
+        cache.write().unwrap().insert(key.to_string(), file.clone());
+        file
+    }
+}
+
+/// Splits `mymod:ruby_ore` into its namespace and the rest, names without
+/// one being in `minecraft`
+fn split_namespace(name: &str) -> (&str, &str) {
+    name.split_once(':').unwrap_or(("minecraft", name))
+}
+
+/// Texture name of a texture variable's value, following references to
+/// other variables. Block textures are named like elsewhere in the
+/// renderer, `oak_log` for `minecraft:block/oak_log`
+fn resolve_texture(textures: &HashMap<String, String>, value: &str) -> Option<String> {
+    let mut value = value;
+    for _ in 0..MAX_TEXTURE_REFERENCES {
+        match value.strip_prefix('#') {
+            Some(variable) => value = textures.get(variable)?,
+            None => {
+                let (namespace, path) = split_namespace(value);
+                let path = path.strip_prefix("block/").unwrap_or(path);
+                return Some(match namespace {
+                    "minecraft" => path.to_string(),
+                    namespace => format!("{}:{}", namespace, path),
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Block states and models built in memory, for the tests of anything
+/// planning blocks from their models
+#[cfg(test)]
+pub(crate) mod test_util {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    pub(crate) fn state(name: &str, properties: &[(&str, &str)]) -> PaletteEntry {
+        PaletteEntry {
+            name: name.to_string(),
+            properties: properties
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    /// Models with the files given instead of read from the assets
+    pub(crate) fn models(
+        blockstates: &[(&str, &str)],
+        model_files: &[(&str, &str)],
+    ) -> BlockModels {
+        let models = BlockModels::new("/nonexistent");
+        for (name, json) in blockstates {
+            let file = serde_json::from_str(json).unwrap();
+            let mut cache = models.blockstates.write().unwrap();
+            cache.insert(name.to_string(), Some(Arc::new(file)));
+        }
+        for (reference, json) in model_files {
+            let file = serde_json::from_str(json).unwrap();
+            let mut cache = models.model_files.write().unwrap();
+            cache.insert(reference.to_string(), Some(Arc::new(file)));
+        }
+        models
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_util::*;
+    use super::*;
+
+    const CUBE: &str = r##"{
+        "elements": [{
+            "from": [0, 0, 0], "to": [16, 16, 16],
+            "faces": {
+                "down": { "texture": "#down" }, "up": { "texture": "#up" },
+                "north": { "texture": "#north" }, "south": { "texture": "#south" },
+                "west": { "texture": "#west" }, "east": { "texture": "#east" }
+            }
+        }]
+    }"##;
+
+    const CUBE_COLUMN: &str = r##"{
+        "parent": "block/cube",
+        "textures": {
+            "down": "#end", "up": "#end",
+            "north": "#side", "south": "#side", "west": "#side", "east": "#side"
+        }
+    }"##;
+
+    fn texture(model: &PlacedModel, face: Direction) -> &str {
+        &model.elements[0].faces[&face].texture
+    }
+
+    #[test]
+    fn variants_resolve_parents_textures_and_rotation() {
+        let models = models(
+            &[(
+                "oak_log",
+                r#"{ "variants": {
+                    "axis=x": { "model": "minecraft:block/oak_log_horizontal", "x": 90, "y": 90 },
+                    "axis=y": { "model": "minecraft:block/oak_log" }
+                } }"#,
+            )],
+            &[
+                ("minecraft:block/cube", CUBE),
+                ("minecraft:block/cube_column", CUBE_COLUMN),
+                (
+                    "minecraft:block/oak_log",
+                    r#"{ "parent": "block/cube_column",
+                         "textures": { "end": "block/oak_log_top", "side": "block/oak_log" } }"#,
+                ),
+                (
+                    "minecraft:block/oak_log_horizontal",
+                    r#"{ "parent": "minecraft:block/cube_column",
+                         "textures": { "end": "minecraft:block/oak_log_top", "side": "block/oak_log" } }"#,
+                ),
+            ],
+        );
+
+        let upright = models.models_for("oak_log", &state("minecraft:oak_log", &[("axis", "y")]));
+        assert_eq!(upright.len(), 1);
+        assert_eq!(texture(&upright[0], Direction::Up), "oak_log_top");
+        assert_eq!(texture(&upright[0], Direction::East), "oak_log");
+        assert_eq!(upright[0].model_face(Direction::East), Direction::East);
+
+        // Lying along X, the rings face east and the bark on top is turned
+        let lying = &models.models_for("oak_log", &state("minecraft:oak_log", &[("axis", "x")]))[0];
+        let east = lying.model_face(Direction::East);
+        assert_eq!(texture(lying, east), "oak_log_top");
+        let top = lying.model_face(Direction::Up);
+        assert_eq!(texture(lying, top), "oak_log");
+        assert_eq!(lying.texture_turns(top), 1);
+
+        assert!(
+            models
+                .models_for("stone", &state("minecraft:stone", &[]))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn multipart_cases_apply_by_condition() {
+        let models = models(
+            &[(
+                "mymod:pipe",
+                r#"{ "multipart": [
+                    { "apply": { "model": "mymod:block/pipe_core" } },
+                    { "when": { "north": "true|side" }, "apply": { "model": "mymod:block/pipe_arm" } },
+                    { "when": { "OR": [{ "east": "true" }, { "up": "true" }] },
+                      "apply": [{ "model": "mymod:block/pipe_arm", "y": 90 }, { "model": "mymod:block/unused" }] }
+                ] }"#,
+            )],
+            &[
+                (
+                    "mymod:block/pipe_core",
+                    r#"{ "parent": "block/cube_all", "textures": { "all": "mymod:block/pipe" } }"#,
+                ),
+                (
+                    "minecraft:block/cube_all",
+                    r##"{ "parent": "block/cube", "textures": {
+                        "down": "#all", "up": "#all", "north": "#all",
+                        "south": "#all", "west": "#all", "east": "#all" } }"##,
+                ),
+                ("minecraft:block/cube", CUBE),
+                (
+                    "mymod:block/pipe_arm",
+                    r##"{ "elements": [{ "from": [6, 6, 0], "to": [10, 10, 6], "faces": {
+                        "up": { "texture": "#arm" }, "south": { "texture": "#missing" } } }],
+                        "textures": { "arm": "mymod:block/pipe_arm" } }"##,
+                ),
+            ],
+        );
+        let pipe = |properties: &[(&str, &str)]| {
+            models.models_for("mymod:pipe", &state("mymod:pipe", properties))
+        };
+
+        assert_eq!(pipe(&[]).len(), 1);
+        assert_eq!(texture(&pipe(&[])[0], Direction::Up), "mymod:pipe");
+        assert_eq!(pipe(&[("north", "side")]).len(), 2);
+        let arms = pipe(&[("north", "true"), ("up", "true")]);
+        assert_eq!(arms.len(), 3);
+        // Faces with unset texture variables are left out
+        assert_eq!(arms[1].elements[0].faces.len(), 1);
+        assert_eq!(arms[2].model_face(Direction::East), Direction::North);
+        assert_eq!(
+            arms[1].elements[0].face_uv(Direction::Up),
+            [6.0, 0.0, 10.0, 6.0]
+        );
+    }
+
+    #[test]
+    fn cross_planes_are_flat_boxes_turned_about_y() {
+        let element = |json: &str| serde_json::from_str::<ModelElement>(json).unwrap();
+
+        let plane = element(
+            r#"{ "from": [0.8, 0, 8], "to": [15.2, 16, 8],
+                 "rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true } }"#,
+        );
+        assert!(plane.is_cross_plane());
+        let other_way = element(
+            r#"{ "from": [8, 0, 0.8], "to": [8, 16, 15.2],
+                 "rotation": { "origin": [8, 8, 8], "axis": "y", "angle": -45 } }"#,
+        );
+        assert!(other_way.is_cross_plane());
+
+        // Flat but facing a side, like a ladder
+        let unturned = element(r#"{ "from": [0, 0, 15.2], "to": [16, 16, 15.2] }"#);
+        assert!(!unturned.is_cross_plane());
+        let about_x = element(
+            r#"{ "from": [0.8, 0, 8], "to": [15.2, 16, 8],
+                 "rotation": { "origin": [8, 8, 8], "axis": "x", "angle": 45 } }"#,
+        );
+        assert!(!about_x.is_cross_plane());
+        let half_turned = element(
+            r#"{ "from": [0.8, 0, 8], "to": [15.2, 16, 8],
+                 "rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 22.5 } }"#,
+        );
+        assert!(!half_turned.is_cross_plane());
+        // A turned box with depth is not a plane
+        let solid = element(
+            r#"{ "from": [4, 0, 4], "to": [12, 16, 12],
+                 "rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 45 } }"#,
+        );
+        assert!(!solid.is_cross_plane());
+    }
+
+    #[test]
+    fn the_most_specific_matching_variant_wins() {
+        let file: BlockstateFile = serde_json::from_str(
+            r#"{ "variants": {
+                "": { "model": "block/any" },
+                "lit=true": { "model": "block/lit" },
+                "facing=north": { "model": "block/north" },
+                "facing=north,lit=true": { "model": "block/north_lit" },
+                "facing=south,lit=true": { "model": "block/south_lit" }
+            } }"#,
+        )
+        .unwrap();
+        let model = |properties: &[(&str, &str)]| {
+            let state = state("minecraft:furnace", properties);
+            file.variants_for(&state)[0].model.clone()
+        };
+
+        assert_eq!(model(&[]), "block/any");
+        assert_eq!(
+            model(&[("facing", "north"), ("lit", "true")]),
+            "block/north_lit"
+        );
+        assert_eq!(model(&[("facing", "south"), ("lit", "false")]), "block/any");
+        assert_eq!(
+            model(&[("facing", "north"), ("lit", "false")]),
+            "block/north"
+        );
+        assert_eq!(model(&[("facing", "east"), ("lit", "true")]), "block/lit");
+
+        // Ties go to the lowest key, not to whichever the map visits first
+        let file: BlockstateFile = serde_json::from_str(
+            r#"{ "variants": {
+                "lit=true": { "model": "block/lit" },
+                "facing=north": { "model": "block/north" }
+            } }"#,
+        )
+        .unwrap();
+        let state = state("minecraft:furnace", &[("facing", "north"), ("lit", "true")]);
+        assert_eq!(file.variants_for(&state)[0].model, "block/north");
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::blocks::{BlockMetadata, BlockProperties, Shape};
+use crate::section::PaletteEntry;
+
+/// Compact ID of an interned block state, valid for the registry that
+/// handed it out
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct BlockId(u32);
+
+/// An interned block state with its metadata looked up once
+#[derive(Debug, Clone)]
+pub struct BlockInfo {
+    pub id: BlockId,
+    pub state: PaletteEntry,
+    /// Name without the `minecraft:` prefix
+    pub name: String,
+    pub properties: BlockProperties,
+    /// Water drawn around a waterlogged block that is not a fluid itself
+    pub water: Option<Box<BlockInfo>>,
+}
+
+impl BlockInfo {
+    /// Whether this block hides the face of `block` it touches. Blocks
+    /// filled with water hide the faces of water against them, so a lake
+    /// shows only its surface
+    pub fn hides_face_of(&self, block: &BlockInfo) -> bool {
+        self.properties.is_opaque()
+            || (self.properties.culls_own_kind && self.name == block.name)
+            || (block.properties.shape == Shape::Fluid && self.holds_fluid_of(block))
+    }
+
+    /// Whether this block holds the same fluid as `fluid`, being the same
+    /// fluid or filled with water like it
+    pub fn holds_fluid_of(&self, fluid: &BlockInfo) -> bool {
+        self.name == fluid.name || (self.is_waterlogged() && fluid.is_waterlogged())
+    }
+
+    /// Whether the block is filled with water
+    pub fn is_waterlogged(&self) -> bool {
+        self.properties.waterlogged || self.state.property("waterlogged") == Some("true")
+    }
+}
+
+/// Every distinct block state of the loaded chunks. Palettes are interned
+/// when chunks are added to the `ChunkStore`, so sections map palette
+/// indices to IDs and rendering looks blocks up by index
+pub struct BlockRegistry {
+    blocks: Vec<BlockInfo>,
+    ids: HashMap<PaletteEntry, BlockId>,
+    metadata: BlockMetadata,
+}
+
+impl BlockRegistry {
+    pub fn new(metadata: BlockMetadata) -> Self {
+        Self {
+            blocks: Vec::new(),
+            ids: HashMap::new(),
+            metadata,
+        }
+    }
+
+    /// ID of a block state, registering it if it is new
+    pub fn intern(&mut self, state: &PaletteEntry) -> BlockId {
+        if let Some(&id) = self.ids.get(state) {
+            return id;
+        }
+
+        let name = state
+            .name
+            .strip_prefix("minecraft:")
+            .unwrap_or(&state.name)
+            .to_string();
+        let properties = self.metadata.properties(&name);
+        let mut block = BlockInfo {
+            id: BlockId(0),
+            state: state.clone(),
+            properties,
+            name,
+            water: None,
+        };
+        if block.is_waterlogged() && block.properties.shape != Shape::Fluid {
+            let water = self.intern(&PaletteEntry {
+                name: "minecraft:water".to_string(),
+                properties: BTreeMap::from([("level".to_string(), "0".to_string())]),
+            });
+            block.water = Some(Box::new(self.get(water).clone()));
+        }
+
+        let id = BlockId(self.blocks.len() as u32);
+        block.id = id;
+        self.blocks.push(block);
+        self.ids.insert(state.clone(), id);
+        id
+    }
+
+    pub fn get(&self, id: BlockId) -> &BlockInfo {
+        &self.blocks[id.0 as usize]
+    }
+
+    /// Number of distinct block states
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_states_share_an_id() {
+        let state = |name: &str, axis: Option<&str>| PaletteEntry {
+            name: name.to_string(),
+            properties: axis
+                .map(|axis| BTreeMap::from([("axis".to_string(), axis.to_string())]))
+                .unwrap_or_default(),
+        };
+        let mut registry = BlockRegistry::new(BlockMetadata::bundled());
+
+        let log_x = registry.intern(&state("minecraft:oak_log", Some("x")));
+        let log_y = registry.intern(&state("minecraft:oak_log", Some("y")));
+        let air = registry.intern(&state("minecraft:air", None));
+        let water = registry.intern(&state("minecraft:water", None));
+        let glass = registry.intern(&state("minecraft:glass", None));
+        assert_ne!(log_x, log_y);
+        assert_eq!(
+            registry.intern(&state("minecraft:oak_log", Some("x"))),
+            log_x
+        );
+        assert_eq!(registry.len(), 5);
+
+        let log = registry.get(log_y);
+        assert_eq!(log.name, "oak_log");
+        assert_eq!(log.state.property("axis"), Some("y"));
+        assert!(log.properties.is_opaque());
+        assert!(registry.get(air).properties.is_invisible());
+
+        let water = registry.get(water);
+        assert!(log.hides_face_of(water));
+        assert!(water.hides_face_of(water));
+        assert!(!water.hides_face_of(registry.get(glass)));
+        assert!(!registry.get(air).hides_face_of(log));
+    }
+
+    #[test]
+    fn waterlogged_blocks_hold_water() {
+        let state = |name: &str, waterlogged: &str| PaletteEntry {
+            name: name.to_string(),
+            properties: BTreeMap::from([("waterlogged".to_string(), waterlogged.to_string())]),
+        };
+        let mut registry = BlockRegistry::new(BlockMetadata::bundled());
+
+        let dry = registry.intern(&state("minecraft:oak_stairs", "false"));
+        let wet = registry.intern(&state("minecraft:oak_stairs", "true"));
+        let kelp = registry.intern(&PaletteEntry {
+            name: "minecraft:kelp_plant".to_string(),
+            properties: BTreeMap::new(),
+        });
+        assert!(registry.get(dry).water.is_none());
+        let water = registry
+            .get(wet)
+            .water
+            .as_deref()
+            .expect("water around the stairs");
+        assert_eq!(water.name, "water");
+        assert_eq!(
+            registry.get(kelp).water.as_ref().map(|water| water.id),
+            Some(water.id)
+        );
+
+        assert!(registry.get(wet).hides_face_of(water));
+        assert!(registry.get(kelp).hides_face_of(water));
+        assert!(!registry.get(dry).hides_face_of(water));
+        assert!(!water.hides_face_of(registry.get(wet)));
+    }
+}
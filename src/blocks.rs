@@ -1,3 +1,223 @@
-pub fn is_air_block(name: &str) -> bool {
-    name == "minecraft:air" || name == "minecraft:cave_air" || name == "minecraft:void_air"
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::biome::TintType;
+use crate::data_file::{DataFileError, NamePattern, read_json};
+
+/// Metadata shipped with the renderer, see `BlockMetadata`
+const BUNDLED_METADATA: &str = include_str!("../data/blocks.json");
+
+/// How much of what is behind a block shows through it
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Transparency {
+    #[default]
+    Opaque,
+    /// Fully see-through pixels, like leaves, glass and plants
+    Transparent,
+    /// Partially see-through pixels, like water and stained glass
+    Translucent,
+    /// Not drawn at all, like air and barriers
+    Invisible,
+}
+
+/// Geometry of a block
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Shape {
+    #[default]
+    Cube,
+    Slab,
+    Stairs,
+    Fence,
+    Wall,
+    Pane,
+    /// Two crossed planes, like flowers and saplings
+    Cross,
+    Fluid,
+    /// Any other shape that does not fill the cube
+    Other,
+}
+
+/// What the renderer needs to know about a block
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockProperties {
+    pub transparency: Transparency,
+    pub shape: Shape,
+    /// Light level the block gives off, 0 to 15
+    pub emission: u8,
+    pub tint: Option<TintType>,
+    /// Texture drawn over both sides, taking the tint instead of them
+    pub side_overlay: Option<String>,
+    /// Hides the faces between blocks of its own kind, like glass and fluids
+    pub culls_own_kind: bool,
+    /// Always filled with water, like water itself, seagrass and kelp. Other
+    /// blocks are filled when their state says so
+    pub waterlogged: bool,
+}
+
+impl BlockProperties {
+    pub fn is_invisible(&self) -> bool {
+        self.transparency == Transparency::Invisible
+    }
+
+    /// Whether the block fills its whole cube with opaque textures, hiding
+    /// the faces of the blocks next to it
+    pub fn is_opaque(&self) -> bool {
+        self.transparency == Transparency::Opaque && self.shape == Shape::Cube
+    }
+}
+
+/// An entry of a metadata file. Unset fields fall through to the next
+/// matching entry
+#[derive(Deserialize, Debug, Clone, Default)]
+struct BlockEntry {
+    transparency: Option<Transparency>,
+    shape: Option<Shape>,
+    emission: Option<u8>,
+    tint: Option<TintType>,
+    side_overlay: Option<String>,
+    culls_own_kind: Option<bool>,
+    waterlogged: Option<bool>,
+}
+
+/// An entry matching every block name with a prefix or suffix
+#[derive(Deserialize, Debug)]
+struct PatternEntry {
+    #[serde(flatten)]
+    pattern: NamePattern,
+    #[serde(flatten)]
+    entry: BlockEntry,
+}
+
+/// A metadata file: entries by block name, and patterns tried in order
+/// for names without one
+#[derive(Deserialize, Debug, Default)]
+struct MetadataFile {
+    #[serde(default)]
+    blocks: HashMap<String, BlockEntry>,
+    #[serde(default)]
+    patterns: Vec<PatternEntry>,
+}
+
+impl MetadataFile {
+    /// The block's own entry, or else the first pattern matching it
+    fn entry_for(&self, name: &str) -> Option<&BlockEntry> {
+        self.blocks.get(name).or_else(|| {
+            self.patterns
+                .iter()
+                .find(|pattern| pattern.pattern.matches(name))
+                .map(|pattern| &pattern.entry)
+        })
+    }
+}
+
+/// Which blocks are opaque, see-through, emissive, tinted or not cubes.
+/// Read from the bundled `data/blocks.json`, with an optional user file in
+/// the same format whose values take precedence. Names are without the
+/// `minecraft:` prefix; blocks of other namespaces keep theirs
+#[derive(Debug)]
+pub struct BlockMetadata {
+    /// Highest precedence first
+    files: Vec<MetadataFile>,
+}
+
+impl BlockMetadata {
+    pub fn bundled() -> Self {
+        let bundled = serde_json::from_str(BUNDLED_METADATA).expect("bundled block metadata");
+        Self {
+            files: vec![bundled],
+        }
+    }
+
+    /// The bundled metadata with the user's overrides on top
+    pub fn load(overrides: Option<&Path>) -> Result<Self, DataFileError> {
+        let mut metadata = Self::bundled();
+        if let Some(path) = overrides {
+            metadata.files.insert(0, read_json(path)?);
+        }
+        Ok(metadata)
+    }
+
+    /// Properties of a block, each taken from the first entry that sets it
+    pub fn properties(&self, name: &str) -> BlockProperties {
+        let mut merged = BlockEntry::default();
+        for entry in self.files.iter().filter_map(|file| file.entry_for(name)) {
+            merged.transparency = merged.transparency.or(entry.transparency);
+            merged.shape = merged.shape.or(entry.shape);
+            merged.emission = merged.emission.or(entry.emission);
+            merged.tint = merged.tint.or(entry.tint);
+            merged.side_overlay = merged.side_overlay.or(entry.side_overlay.clone());
+            merged.culls_own_kind = merged.culls_own_kind.or(entry.culls_own_kind);
+            merged.waterlogged = merged.waterlogged.or(entry.waterlogged);
+        }
+
+        BlockProperties {
+            transparency: merged.transparency.unwrap_or_default(),
+            shape: merged.shape.unwrap_or_default(),
+            emission: merged.emission.unwrap_or(0).min(15),
+            tint: merged.tint,
+            side_overlay: merged.side_overlay,
+            culls_own_kind: merged.culls_own_kind.unwrap_or(false),
+            waterlogged: merged.waterlogged.unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_metadata_describes_blocks() {
+        let metadata = BlockMetadata::bundled();
+
+        assert!(metadata.properties("stone").is_opaque());
+        assert!(metadata.properties("crimson_stem").is_opaque());
+        assert!(metadata.properties("sea_lantern").is_opaque());
+        assert!(metadata.properties("air").is_invisible());
+        assert_eq!(metadata.properties("melon_stem").shape, Shape::Cross);
+        assert_eq!(metadata.properties("stone_brick_slab").shape, Shape::Slab);
+        assert!(!metadata.properties("oak_leaves").is_opaque());
+        assert!(!metadata.properties("potted_fern").is_opaque());
+
+        let water = metadata.properties("water");
+        assert_eq!(water.transparency, Transparency::Translucent);
+        assert_eq!(water.tint, Some(TintType::Water));
+        assert!(water.culls_own_kind);
+        assert!(water.waterlogged);
+        assert!(metadata.properties("kelp_plant").waterlogged);
+        assert!(!metadata.properties("oak_stairs").waterlogged);
+        assert_eq!(metadata.properties("glowstone").emission, 15);
+        assert_eq!(
+            metadata.properties("spruce_leaves").tint,
+            Some(TintType::Fixed(0x619961))
+        );
+    }
+
+    #[test]
+    fn user_metadata_overrides_bundled_values() {
+        let user: MetadataFile = serde_json::from_str(
+            r##"{
+                "blocks": { "stone": { "emission": 4 } },
+                "patterns": [{ "prefix": "mymod:", "suffix": "_lamp", "emission": 15 }]
+            }"##,
+        )
+        .unwrap();
+        let mut metadata = BlockMetadata::bundled();
+        metadata.files.insert(0, user);
+
+        let stone = metadata.properties("stone");
+        assert_eq!(stone.emission, 4);
+        assert!(stone.is_opaque());
+        assert_eq!(metadata.properties("mymod:red_lamp").emission, 15);
+        assert_eq!(metadata.properties("mymod:red_lamp").shape, Shape::Cube);
+        // Values the user does not set come from the bundled file
+        assert_eq!(
+            metadata.properties("grass_block").side_overlay.as_deref(),
+            Some("grass_block_side_overlay")
+        );
+    }
 }
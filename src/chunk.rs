@@ -1,32 +1,164 @@
 use serde::Deserialize;
 
-use crate::section::Section;
+use crate::block_registry::BlockRegistry;
+use crate::heightmap::Heightmaps;
+use crate::legacy::{self, LegacyLevel};
+use crate::region_error::ChunkError;
+use crate::section::{BitPacking, Section};
 
+/// First data version (21w43a) storing sections at the top level instead
+/// of under `Level`
+pub const FLAT_LAYOUT_VERSION: i32 = 2844;
+
+/// A chunk in the 1.18+ layout. Older layouts are converted to it while
+/// deserializing, see `ChunkNbt`
 #[derive(Deserialize, Debug)]
-#[serde(rename_all = "PascalCase")]
+#[serde(try_from = "ChunkNbt")]
 pub struct Chunk {
-    // pub data_version: i32,
-    // #[serde(rename = "xPos")]
-    // pub x_pos: i32,
-    // #[serde(rename = "zPos")]
-    // pub z_pos: i32,
+    /// Absent in chunks older than 1.9
+    pub data_version: Option<i32>,
+    pub x_pos: i32,
+    pub z_pos: i32,
     /// Lowest Y section position in the chunk.
-    /// `-4` in modern versions meaning Y=-64
-    // #[serde(rename = "yPos")]
-    // pub y_pos: i32,
+    /// `-4` in modern versions meaning Y=-64. Absent before 1.18
+    pub y_pos: Option<i32>,
 
     /// Status of the chunk generation process.
-    /// `minecraft:full` means fully generated.
-    // pub status: String,
-
-    #[serde(rename = "sections")]
+    /// `minecraft:full` means fully generated. Absent before 1.13
+    pub status: Option<String>,
     pub sections: Vec<Section>,
+    /// Absent before 1.13 and in some proto-chunks
+    pub heightmaps: Option<Heightmaps>,
 }
 
 impl Chunk {
-    pub fn ensure_unpacked(&mut self) {
+    /// Whether generation finished. Proto-chunks at the edge of explored
+    /// terrain stop at an earlier status like `minecraft:features`
+    pub fn is_fully_generated(&self) -> bool {
+        let Some(status) = &self.status else {
+            return true;
+        };
+        let status = status.strip_prefix("minecraft:").unwrap_or(status);
+        // 1.13 finished with `fullchunk` and then `postprocessed`
+        matches!(status, "full" | "fullchunk" | "postprocessed")
+    }
+
+    /// Lowest block Y of the chunk, which its heightmaps count from
+    pub fn min_y(&self) -> isize {
+        self.y_pos.unwrap_or(0) as isize * 16
+    }
+
+    /// Unpack the block states of every section and the heightmaps, in the
+    /// bit layout of the chunk's version
+    pub fn ensure_unpacked(&mut self) -> Result<(), ChunkError> {
+        let packing = BitPacking::for_version(self.data_version);
+        if let Some(heightmaps) = &mut self.heightmaps {
+            heightmaps
+                .ensure_unpacked(packing)
+                .map_err(ChunkError::InvalidHeightmap)?;
+        }
         for section in &mut self.sections {
-            section.ensure_unpacked();
+            section
+                .ensure_unpacked(packing)
+                .map_err(|error| ChunkError::InvalidSection {
+                    section_y: section.y,
+                    error,
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Register the palette of every section, mapping them to block IDs
+    pub fn intern_blocks(&mut self, registry: &mut BlockRegistry) {
+        for states in self
+            .sections
+            .iter_mut()
+            .filter_map(|section| section.block_states.as_mut())
+        {
+            states.intern_palette(registry);
+        }
+    }
+}
+
+/// The root compound of a chunk in any version. Which layout is read is
+/// decided by the `DataVersion`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChunkNbt {
+    data_version: Option<i32>,
+    #[serde(rename = "xPos")]
+    x_pos: Option<i32>,
+    #[serde(rename = "zPos")]
+    z_pos: Option<i32>,
+    #[serde(rename = "yPos")]
+    y_pos: Option<i32>,
+    status: Option<String>,
+    #[serde(rename = "sections")]
+    sections: Option<Vec<Section>>,
+    heightmaps: Option<Heightmaps>,
+    /// Everything before 21w43a is nested in here
+    level: Option<LegacyLevel>,
+}
+
+impl TryFrom<ChunkNbt> for Chunk {
+    type Error = String;
+
+    fn try_from(nbt: ChunkNbt) -> Result<Self, Self::Error> {
+        let data_version = nbt.data_version;
+        let flat = match data_version {
+            Some(version) => version >= FLAT_LAYOUT_VERSION,
+            None => nbt.level.is_none(),
+        };
+
+        if !flat {
+            let level = nbt
+                .level
+                .ok_or_else(|| format!("chunk version {:?} has no Level compound", data_version))?;
+            return Ok(legacy::convert_level(level, data_version));
         }
+
+        match (nbt.x_pos, nbt.z_pos, nbt.sections) {
+            (Some(x_pos), Some(z_pos), Some(sections)) => Ok(Chunk {
+                data_version,
+                x_pos,
+                z_pos,
+                y_pos: nbt.y_pos,
+                status: nbt.status,
+                sections,
+                heightmaps: nbt.heightmaps,
+            }),
+            _ => Err("chunk is missing its position or sections".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fastnbt::nbt;
+
+    use super::*;
+
+    #[test]
+    fn chunk_status_decides_full_generation() {
+        let modern = |status: &str| -> Chunk {
+            fastnbt::from_value(&nbt!({
+                "DataVersion": 3953,
+                "xPos": 0,
+                "zPos": 0,
+                "Status": status,
+                "sections": [],
+            }))
+            .unwrap()
+        };
+        assert!(modern("minecraft:full").is_fully_generated());
+        assert!(!modern("minecraft:features").is_fully_generated());
+
+        let legacy: Chunk = fastnbt::from_value(&nbt!({
+            "DataVersion": 1631,
+            "Level": {"xPos": 0, "zPos": 0, "Status": "postprocessed"},
+        }))
+        .unwrap();
+        assert_eq!(legacy.status.as_deref(), Some("postprocessed"));
+        assert!(legacy.is_fully_generated());
     }
 }
@@ -1,25 +1,39 @@
 use std::collections::HashMap;
 
+use crate::block_registry::{BlockId, BlockInfo, BlockRegistry};
+use crate::blocks::BlockMetadata;
 use crate::coords::chunk_local_block_coord::ChunkLocalBlockCoord;
 use crate::section::Section;
+use crate::world::dimension::SkyLight;
 use crate::{
     chunk::Chunk,
     coords::{world_block_coord::WorldBlockCoord, world_chunk_coord::WorldChunkCoord},
 };
 
+/// How far under an overhang, in blocks toward the camera and down through
+/// its roof, the ground below it is looked for
+const MAX_OVERHANG_DEPTH: isize = 16;
+
 pub struct ChunkStore {
     pub chunks: HashMap<WorldChunkCoord, Chunk>,
+    /// Block states of all chunks in the store
+    pub registry: BlockRegistry,
+    sky_light: SkyLight,
 }
 
 impl ChunkStore {
-    pub fn new() -> Self {
+    pub fn new(sky_light: SkyLight, metadata: BlockMetadata) -> Self {
         Self {
             chunks: HashMap::new(),
+            registry: BlockRegistry::new(metadata),
+            sky_light,
         }
     }
 
+    /// Add a chunk, which must already be unpacked like those returned by
+    /// `RegionFile::read_chunks`. Its block states are interned
     pub fn insert(&mut self, coord: WorldChunkCoord, mut chunk: Chunk) {
-        chunk.ensure_unpacked();
+        chunk.intern_blocks(&mut self.registry);
         self.chunks.insert(coord, chunk);
     }
 
@@ -40,11 +54,132 @@ impl ChunkStore {
         chunk.sections.iter().find(|s| s.y == section.y + 1)
     }
 
-    /// Get block at world coordinates
-    pub fn get_block_at(&self, block_coords: &WorldBlockCoord) -> Option<String> {
+    /// Get the block state ID at world coordinates
+    pub fn get_block_at(&self, block_coords: &WorldBlockCoord) -> Option<BlockId> {
+        let local_coords = block_coords.section_local_coord();
+        let section = self.get_section(block_coords)?;
+        section.block_id_at(local_coords)
+    }
+
+    /// Get the interned block state at world coordinates
+    pub fn get_block_info_at(&self, block_coords: &WorldBlockCoord) -> Option<&BlockInfo> {
+        self.get_block_at(block_coords)
+            .map(|id| self.registry.get(id))
+    }
+
+    pub fn get_biome_at(&self, block_coords: &WorldBlockCoord) -> Option<&str> {
         let local_coords = block_coords.section_local_coord();
         let section = self.get_section(block_coords)?;
-        section.block_at(local_coords).map(|p| p.name.clone())
+        section.biome_at(local_coords)
+    }
+
+    /// Y of the highest non-air and the highest opaque block of a column,
+    /// from the chunk's heightmaps
+    fn get_column_heights(&self, column: &WorldBlockCoord) -> Option<(isize, isize)> {
+        let chunk = self.get(column.chunk_coord())?;
+        let heightmaps = chunk.heightmaps.as_ref()?;
+        let local_coords = column.section_local_coord();
+        let index = local_coords.lz * 16 + local_coords.lx;
+        let surface = heightmaps.surface_y(index, chunk.min_y())?;
+
+        // Blocks that stop motion without hiding what is under them, like
+        // fences, slabs and glass, leave the floor lower
+        let mut floor = heightmaps.floor_y(index, chunk.min_y())?;
+        while floor > chunk.min_y()
+            && self
+                .get_block_info_at(&WorldBlockCoord {
+                    y: floor,
+                    ..*column
+                })
+                .is_some_and(|block| !block.properties.is_opaque())
+        {
+            floor -= 1;
+        }
+        Some((surface, floor))
+    }
+
+    /// Lowest and highest Y of a column that can be seen when rendering below
+    /// `max_y` (exclusive). `None` if the heightmaps cannot tell, and every
+    /// block of the column has to be considered. Caves are assumed hidden
+    /// unless they open toward the camera within `MAX_OVERHANG_DEPTH`
+    /// blocks, so those cut open at the edge of the rendered area show as
+    /// holes
+    pub fn get_visible_y_range(
+        &self,
+        column: &WorldBlockCoord,
+        max_y: isize,
+    ) -> Option<(isize, isize)> {
+        let (surface, floor) = self.get_column_heights(column)?;
+        let (east_surface, east_floor) = self.get_column_heights(&column.east_pos_x())?;
+        let (south_surface, south_floor) = self.get_column_heights(&column.south_pos_z())?;
+
+        // A cut through the terrain exposes what is below the surface
+        if surface.max(east_surface).max(south_surface) >= max_y {
+            return None;
+        }
+
+        // A block shows its top above its own column's floor, and also at
+        // the floor of the column next to it, seen through the space above
+        // that, and its sides above the floor of the column next to them
+        let mut lowest = floor.min(east_floor).min(south_floor);
+        if !self.is_open_below(column, floor) {
+            return Some((lowest, surface));
+        }
+
+        // Under an overhang the view ray rises one block per column toward
+        // the camera until it leaves through the side, where the ground is
+        // lower than the overhang. Unloaded columns end the walk
+        'walk: for k in 1..=MAX_OVERHANG_DEPTH {
+            let ahead = WorldBlockCoord {
+                x: column.x + k,
+                y: column.y,
+                z: column.z + k,
+            };
+            for ahead in [ahead, ahead.east_pos_x(), ahead.south_pos_z()] {
+                let Some((ahead_surface, ahead_floor)) = self.get_column_heights(&ahead) else {
+                    break 'walk;
+                };
+                if ahead_surface >= max_y {
+                    return None;
+                }
+                lowest = lowest.min(ahead_floor - k);
+            }
+        }
+        Some((lowest, surface))
+    }
+
+    /// Whether a block that does not hide what is behind it lies within
+    /// `MAX_OVERHANG_DEPTH` blocks below the floor of a column
+    fn is_open_below(&self, column: &WorldBlockCoord, floor: isize) -> bool {
+        (1..=MAX_OVERHANG_DEPTH).any(|depth| {
+            self.get_block_info_at(&WorldBlockCoord {
+                y: floor - depth,
+                ..*column
+            })
+            .is_some_and(|block| !block.properties.is_opaque())
+        })
+    }
+
+    /// Blocks from a block of water down to the first block below it not
+    /// filled with the same water, counting the block itself, up to
+    /// `max_depth`. Unloaded blocks end the count too
+    pub fn get_water_depth_at(&self, coords: &WorldBlockCoord, max_depth: u8) -> u8 {
+        let Some(block) = self.get_block_info_at(coords) else {
+            return 0;
+        };
+        let water = block.water.as_deref().unwrap_or(block);
+
+        let mut depth = 0;
+        let mut below = *coords;
+        while depth < max_depth
+            && self
+                .get_block_info_at(&below)
+                .is_some_and(|block| block.holds_fluid_of(water))
+        {
+            depth += 1;
+            below = below.bottom_neg_y();
+        }
+        depth
     }
 
     pub fn get_block_light_at(&self, block_coords: &WorldBlockCoord) -> Option<u8> {
@@ -54,6 +189,10 @@ impl ChunkStore {
     }
 
     pub fn get_sky_light_at(&self, coords: &WorldBlockCoord) -> u8 {
+        if let SkyLight::Ambient(level) = self.sky_light {
+            return level;
+        }
+
         let local_coords = coords.section_local_coord();
         let Some(mut section) = self.get_section(coords) else {
             return 0xF;
@@ -72,7 +211,7 @@ impl ChunkStore {
         loop {
             // If there wasn't sky light there, then we need to look at the section above
             // If there is no section above, then we return full brightness
-            if let Some(section_above) = self.get_section_above(&section, coords.chunk_coord()) {
+            if let Some(section_above) = self.get_section_above(section, coords.chunk_coord()) {
                 section = section_above;
                 // If we found a section above, then we need to examine it for sky light at the
                 // lowest y-value in the section
@@ -109,3 +248,156 @@ impl ChunkStore {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use fastnbt::nbt;
+
+    use super::*;
+    use crate::heightmap::Heightmaps;
+    use crate::world::dimension::{DimensionId, END_AMBIENT_LIGHT, NETHER_AMBIENT_LIGHT};
+
+    /// A store with a chunk at 0, 0 holding `sections`
+    fn store_with(sky_light: SkyLight, sections: Vec<fastnbt::Value>) -> ChunkStore {
+        let mut chunk: Chunk = fastnbt::from_value(&nbt!({
+            "DataVersion": 3953,
+            "xPos": 0,
+            "zPos": 0,
+            "sections": sections,
+        }))
+        .unwrap();
+        chunk.ensure_unpacked().unwrap();
+
+        let mut store = ChunkStore::new(sky_light, BlockMetadata::bundled());
+        store.insert(WorldChunkCoord { cx: 0, cz: 0 }, chunk);
+        store
+    }
+
+    #[test]
+    fn dimensions_without_sky_use_their_ambient_light() {
+        // Stone at sky light 3 in the section at Y 0 to 15
+        let sections = || {
+            vec![nbt!({
+                "Y": 0i8,
+                "block_states": { "palette": [{ "Name": "minecraft:stone" }] },
+                "SkyLight": fastnbt::ByteArray::new(vec![0x33; 2048]),
+            })]
+        };
+        let stored = WorldBlockCoord { x: 3, y: 5, z: 5 };
+        let above = WorldBlockCoord { x: 3, y: 100, z: 5 };
+
+        let overworld = store_with(DimensionId::Overworld.sky_light(), sections());
+        assert_eq!(overworld.get_sky_light_at(&stored), 3);
+        assert_eq!(overworld.get_sky_light_at(&above), 0xF);
+
+        let nether = store_with(DimensionId::Nether.sky_light(), sections());
+        assert_eq!(nether.get_sky_light_at(&stored), NETHER_AMBIENT_LIGHT);
+        assert_eq!(nether.get_sky_light_at(&above), NETHER_AMBIENT_LIGHT);
+
+        let end = store_with(DimensionId::End.sky_light(), sections());
+        assert_eq!(end.get_sky_light_at(&stored), END_AMBIENT_LIGHT);
+        assert_eq!(end.get_sky_light_at(&above), END_AMBIENT_LIGHT);
+    }
+
+    /// A store whose sections are each filled with a block, from Y 0 up
+    fn layered_store(layers: &[&str]) -> ChunkStore {
+        let sections = layers
+            .iter()
+            .enumerate()
+            .map(|(y, name)| {
+                nbt!({
+                    "Y": y as i8,
+                    "block_states": { "palette": [{ "Name": *name }] },
+                })
+            })
+            .collect();
+        store_with(SkyLight::Stored, sections)
+    }
+
+    #[test]
+    fn water_depth_counts_down_to_the_first_block_without_water() {
+        let at = |y| WorldBlockCoord { x: 3, y, z: 5 };
+
+        let store = layered_store(&[
+            "minecraft:stone",
+            "minecraft:glass",
+            "minecraft:water",
+            "minecraft:seagrass",
+        ]);
+        // Seagrass is filled with the same water, the glass below is not
+        assert_eq!(store.get_water_depth_at(&at(63), 64), 32);
+        assert_eq!(store.get_water_depth_at(&at(40), 64), 9);
+        assert_eq!(store.get_water_depth_at(&at(63), 20), 20);
+
+        // Water floating over air ends at the air
+        let store = layered_store(&["minecraft:stone", "minecraft:air", "minecraft:water"]);
+        assert_eq!(store.get_water_depth_at(&at(47), 64), 16);
+
+        // Unloaded blocks end the count
+        let store = layered_store(&["minecraft:water"]);
+        assert_eq!(store.get_water_depth_at(&at(15), 64), 16);
+        assert_eq!(store.get_water_depth_at(&at(100), 64), 0);
+    }
+
+    #[test]
+    fn ground_under_an_overhang_facing_the_camera_is_visible() {
+        // Ground up to Y 52, and a roof at Y 60 and 61 over the columns
+        // below X 8 and Z 8, open toward the camera to the south-east
+        let covered = |x: usize, z: usize| x < 8 && z < 8;
+        let mut data = vec![0; 4096];
+        for (index, state) in data.iter_mut().enumerate() {
+            let (x, z, y) = (index % 16, index / 16 % 16, 48 + index / 256);
+            if y <= 52 || (covered(x, z) && (60..=61).contains(&y)) {
+                *state = 1;
+            }
+        }
+        let heights: Vec<u16> = (0..256)
+            .map(|index| {
+                if covered(index % 16, index / 16) {
+                    62
+                } else {
+                    53
+                }
+            })
+            .collect();
+
+        let mut store = store_with(
+            SkyLight::Stored,
+            vec![nbt!({
+                "Y": 3i8,
+                "block_states": {
+                    "palette": [{ "Name": "minecraft:air" }, { "Name": "minecraft:stone" }],
+                },
+            })],
+        );
+        let chunk = store
+            .chunks
+            .get_mut(&WorldChunkCoord { cx: 0, cz: 0 })
+            .unwrap();
+        chunk.sections[0]
+            .block_states
+            .as_mut()
+            .unwrap()
+            .unpacked_data = Some(data);
+        chunk.heightmaps = Some(Heightmaps {
+            world_surface: None,
+            motion_blocking: None,
+            motion_blocking_no_leaves: None,
+            ocean_floor: None,
+            unpacked_surface: Some(heights.clone()),
+            unpacked_floor: Some(heights),
+        });
+        let range = |x, z| store.get_visible_y_range(&WorldBlockCoord { x, y: 0, z }, 320);
+
+        // Three columns in, the ground is seen from the open side
+        let (lowest, highest) = range(5, 5).unwrap();
+        assert!(lowest <= 52);
+        assert_eq!(highest, 61);
+        assert!(range(0, 0).unwrap().0 <= 52);
+
+        // Open ground, even right in front of the overhang, keeps its floor
+        assert_eq!(range(12, 12), Some((52, 52)));
+        assert_eq!(range(12, 3), Some((52, 52)));
+        assert_eq!(range(8, 8), Some((52, 52)));
+    }
+}
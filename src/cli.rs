@@ -0,0 +1,360 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::coords::{
+    chunk_bounds::ChunkBounds, region_coord::RegionCoord, world_block_coord::WorldBlockCoord,
+    world_chunk_coord::WorldChunkCoord,
+};
+use crate::render::mode::RenderMode;
+use crate::texture_animation::AnimationFrame;
+use crate::world::dimension::DimensionId;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Isometric renderer for Minecraft worlds")]
+pub struct Cli {
+    /// Number of worker threads (defaults to one per CPU)
+    #[arg(short = 'j', long, global = true)]
+    pub threads: Option<usize>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Render an area of a world to a PNG image
+    Render(RenderArgs),
+    /// Print world information, or the contents of a region, chunk or block
+    Inspect(InspectArgs),
+    /// Count the blocks in an area of a world
+    Stats(StatsArgs),
+    /// Check every region file of a world for corruption
+    Scan(ScanArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct WorldArgs {
+    /// World directory (the folder containing level.dat)
+    #[arg(short, long, value_parser = existing_dir)]
+    pub world: PathBuf,
+
+    /// Dimension to read: overworld, nether, end or a datapack id like `mypack:mining`
+    #[arg(short, long, default_value = "overworld")]
+    pub dimension: DimensionId,
+}
+
+#[derive(Args, Debug)]
+pub struct AreaArgs {
+    /// Chunk bounding box as MIN_X,MIN_Z,MAX_X,MAX_Z (inclusive).
+    /// Without a bounding box every generated chunk is used
+    #[arg(long, allow_hyphen_values = true, conflicts_with = "blocks")]
+    pub chunks: Option<ChunkBounds>,
+
+    /// Block bounding box as MIN_X,MIN_Z,MAX_X,MAX_Z, widened to whole chunks
+    #[arg(long, allow_hyphen_values = true)]
+    pub blocks: Option<BlockBounds>,
+
+    /// Leave out chunks outside the world border
+    #[arg(long)]
+    pub clip_to_border: bool,
+}
+
+impl AreaArgs {
+    /// The requested area, or `None` to use the whole world
+    pub fn chunk_bounds(&self) -> Option<ChunkBounds> {
+        self.chunks
+            .or_else(|| self.blocks.map(|blocks| blocks.chunk_bounds()))
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct RenderArgs {
+    #[command(flatten)]
+    pub world: WorldArgs,
+
+    #[command(flatten)]
+    pub area: AreaArgs,
+
+    /// Directory containing the extracted assets: `minecraft/textures`, and
+    /// `minecraft/blockstates` and `minecraft/models` to draw blocks by their
+    /// models rather than by name
+    #[arg(short, long, default_value = "assets", value_parser = existing_dir)]
+    pub assets: PathBuf,
+
+    /// Path of the PNG image to write
+    #[arg(short, long, default_value = "out/world.png")]
+    pub output: PathBuf,
+
+    /// JSON file of block metadata, in the format of the bundled
+    /// `data/blocks.json`, overriding its values
+    #[arg(long)]
+    pub block_metadata: Option<PathBuf>,
+
+    /// JSON file mapping blocks to textures, in the format of the bundled
+    /// `data/textures.json`, taking precedence over its entries
+    #[arg(long)]
+    pub texture_map: Option<PathBuf>,
+
+    /// Frame of animated textures like water and fire to draw, counted
+    /// from 0, or `average` to blend every frame by how long it shows
+    #[arg(long, default_value = "0")]
+    pub animation_frame: AnimationFrame,
+
+    /// Width of a block in the image in pixels, a multiple of 8. Defaults
+    /// to one and a half times the resolution of the textures, 24 pixels
+    /// for 16 pixel textures
+    #[arg(long, value_parser = parse_sprite_size)]
+    pub sprite_size: Option<u32>,
+
+    /// Highest Y to render, cutting away everything above it.
+    /// Defaults to just below the bedrock roof in the Nether
+    #[arg(long, allow_hyphen_values = true)]
+    pub max_y: Option<isize>,
+
+    /// Lighting used when shading blocks
+    #[arg(short, long, value_enum, default_value_t = LightingMode::Day)]
+    pub mode: LightingMode,
+
+    /// What to do with chunks whose generation did not finish, found at the
+    /// edge of explored terrain
+    #[arg(long, value_enum, default_value_t = PartialChunks::Hide)]
+    pub partial_chunks: PartialChunks,
+
+    /// Shade water by its depth, darker and less see-through the further
+    /// it is to the ground below
+    #[arg(long)]
+    pub water_depth: bool,
+}
+
+impl RenderArgs {
+    pub fn render_mode(&self) -> RenderMode {
+        RenderMode {
+            night: self.mode == LightingMode::Night,
+            dim_partial_chunks: self.partial_chunks == PartialChunks::Dim,
+            water_depth: self.water_depth,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+#[group(multiple = false, args = ["region", "chunk", "block"])]
+pub struct InspectArgs {
+    #[command(flatten)]
+    pub world: WorldArgs,
+
+    /// Region file to summarize, as X,Z region coordinates
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_region_coord)]
+    pub region: Option<RegionCoord>,
+
+    /// Chunk to describe, as X,Z
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_chunk_coord)]
+    pub chunk: Option<WorldChunkCoord>,
+
+    /// Block to describe, as X,Y,Z
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_block_coord)]
+    pub block: Option<WorldBlockCoord>,
+}
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    #[command(flatten)]
+    pub world: WorldArgs,
+
+    #[command(flatten)]
+    pub area: AreaArgs,
+
+    /// Number of block types to list
+    #[arg(long, default_value_t = 20)]
+    pub top: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct ScanArgs {
+    /// World directory (the folder containing level.dat)
+    #[arg(short, long, value_parser = existing_dir)]
+    pub world: PathBuf,
+
+    /// Only scan this dimension (defaults to every dimension present)
+    #[arg(short, long)]
+    pub dimension: Option<DimensionId>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightingMode {
+    Day,
+    Night,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialChunks {
+    /// Leave them out of the render
+    Hide,
+    /// Render them darkened
+    Dim,
+    /// Render them like any other chunk
+    Show,
+}
+
+impl FromStr for ChunkBounds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [x1, z1, x2, z2] = parse_list(s)?;
+        Ok(Self {
+            min: WorldChunkCoord {
+                cx: x1.min(x2),
+                cz: z1.min(z2),
+            },
+            max: WorldChunkCoord {
+                cx: x1.max(x2),
+                cz: z1.max(z2),
+            },
+        })
+    }
+}
+
+/// Inclusive rectangle of blocks on the X/Z plane
+#[derive(Debug, Clone, Copy)]
+pub struct BlockBounds {
+    pub min_x: isize,
+    pub min_z: isize,
+    pub max_x: isize,
+    pub max_z: isize,
+}
+
+impl BlockBounds {
+    /// Smallest set of chunks containing every block of the rectangle
+    pub fn chunk_bounds(&self) -> ChunkBounds {
+        ChunkBounds::from_blocks(
+            WorldBlockCoord {
+                x: self.min_x,
+                y: 0,
+                z: self.min_z,
+            },
+            WorldBlockCoord {
+                x: self.max_x,
+                y: 0,
+                z: self.max_z,
+            },
+        )
+    }
+}
+
+impl FromStr for BlockBounds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [x1, z1, x2, z2] = parse_list(s)?;
+        Ok(Self {
+            min_x: x1.min(x2),
+            min_z: z1.min(z2),
+            max_x: x1.max(x2),
+            max_z: z1.max(z2),
+        })
+    }
+}
+
+fn parse_list<const N: usize>(s: &str) -> Result<[isize; N], String> {
+    let values = s
+        .split(',')
+        .map(|part| {
+            part.trim()
+                .parse::<isize>()
+                .map_err(|_| format!("`{}` is not an integer", part.trim()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    values.try_into().map_err(|values: Vec<isize>| {
+        format!(
+            "expected {} comma-separated values, got {}",
+            N,
+            values.len()
+        )
+    })
+}
+
+fn parse_region_coord(s: &str) -> Result<RegionCoord, String> {
+    let [rx, rz] = parse_list(s)?;
+    Ok(RegionCoord { rx, rz })
+}
+
+fn parse_chunk_coord(s: &str) -> Result<WorldChunkCoord, String> {
+    let [cx, cz] = parse_list(s)?;
+    Ok(WorldChunkCoord { cx, cz })
+}
+
+fn parse_block_coord(s: &str) -> Result<WorldBlockCoord, String> {
+    let [x, y, z] = parse_list(s)?;
+    Ok(WorldBlockCoord { x, y, z })
+}
+
+fn parse_sprite_size(s: &str) -> Result<u32, String> {
+    let size = s
+        .parse::<u32>()
+        .map_err(|_| format!("`{}` is not a number of pixels", s))?;
+    if size == 0 || size % 8 != 0 {
+        return Err(format!("{} is not a positive multiple of 8", size));
+    }
+    Ok(size)
+}
+
+fn existing_dir(s: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(s);
+    if !path.exists() {
+        Err(format!("`{}` does not exist", s))
+    } else if !path.is_dir() {
+        Err(format!("`{}` is not a directory", s))
+    } else {
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_parse_in_any_corner_order() {
+        let chunks: ChunkBounds = "30,-2,37,5".parse().unwrap();
+        assert_eq!(chunks.min, WorldChunkCoord { cx: 30, cz: -2 });
+        assert_eq!(chunks.max, WorldChunkCoord { cx: 37, cz: 5 });
+
+        // Corners given max first are swapped
+        let swapped: ChunkBounds = "37,5,30,-2".parse().unwrap();
+        assert_eq!(swapped, chunks);
+
+        let blocks: BlockBounds = " -100, 20 ,-300,40".parse().unwrap();
+        assert_eq!(
+            (blocks.min_x, blocks.min_z, blocks.max_x, blocks.max_z),
+            (-300, 20, -100, 40)
+        );
+        assert_eq!(
+            blocks.chunk_bounds(),
+            ChunkBounds {
+                min: WorldChunkCoord { cx: -19, cz: 1 },
+                max: WorldChunkCoord { cx: -7, cz: 2 },
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_lists_are_rejected() {
+        assert_eq!(
+            "1,2,3".parse::<ChunkBounds>().unwrap_err(),
+            "expected 4 comma-separated values, got 3"
+        );
+        assert!("1,2,3,4,5".parse::<BlockBounds>().is_err());
+        assert_eq!(
+            "1,a,3,4".parse::<ChunkBounds>().unwrap_err(),
+            "`a` is not an integer"
+        );
+        assert!("1.5,2,3,4".parse::<BlockBounds>().is_err());
+        assert!("".parse::<ChunkBounds>().is_err());
+
+        let block = parse_block_coord("-5,64,-7").unwrap();
+        assert_eq!((block.x, block.y, block.z), (-5, 64, -7));
+        assert!(parse_region_coord("0").is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{
+    chunk_store::ChunkStore,
+    cli::InspectArgs,
+    commands::{CommandError, load_chunk, open_dimension},
+    coords::{
+        region_coord::RegionCoord, world_block_coord::WorldBlockCoord,
+        world_chunk_coord::WorldChunkCoord,
+    },
+    region::{RegionFile, SECTOR_SIZE},
+    world::World,
+};
+
+pub fn run(args: &InspectArgs) -> Result<(), CommandError> {
+    let (world, dimension) = open_dimension(&args.world)?;
+    let region_dir = dimension.region_dir();
+
+    if let Some(region) = args.region {
+        if let Some(region) = open_region(&region_dir, region)? {
+            print_region(&region);
+        }
+    } else if let Some(chunk) = args.chunk {
+        if let Some(region) = open_region(&region_dir, chunk.region_coord())? {
+            print_chunk(&region, chunk);
+        }
+    } else if let Some(block) = args.block {
+        let (store, summary) = load_chunk(&dimension, block.chunk_coord())?;
+        print_block(&store, block);
+        summary.print();
+    } else {
+        print_world(&world);
+    }
+
+    Ok(())
+}
+
+fn print_world(world: &World) {
+    let level = &world.level;
+    println!("World \"{}\" at {}", level.level_name, world.path.display());
+    if let Some(data_version) = level.data_version {
+        println!("  data version: {}", data_version);
+    }
+    if let Some(seed) = level.seed() {
+        println!("  seed: {}", seed);
+    }
+    if let Some(spawn) = level.spawn() {
+        println!("  spawn: {}", spawn);
+    }
+    print!("  game time: {} ticks", level.time);
+    match level.day_time {
+        Some(day_time) => println!(" (day time {})", day_time),
+        None => println!(),
+    }
+
+    println!("{} dimensions", world.dimensions.len());
+    for dimension in &world.dimensions {
+        print!("  {} in {}", dimension.id, dimension.region_dir().display());
+        match world.border(dimension) {
+            Some(border) => println!(
+                ", border {} blocks wide centred on {},{}",
+                border.size, border.center_x, border.center_z
+            ),
+            None => println!(),
+        }
+    }
+}
+
+fn open_region(region_dir: &Path, coord: RegionCoord) -> Result<Option<RegionFile>, CommandError> {
+    let path = region_dir.join(coord.file_name());
+    if !path.is_file() {
+        println!("Region file {} not found", coord.file_name());
+        return Ok(None);
+    }
+    Ok(Some(RegionFile::open(&path)?))
+}
+
+fn print_region(region: &RegionFile) {
+    let present: Vec<usize> = region
+        .locations()
+        .iter()
+        .enumerate()
+        .filter(|(_, location)| location.is_present())
+        .map(|(index, _)| index)
+        .collect();
+    let sectors: usize = present
+        .iter()
+        .map(|&index| region.locations()[index].sector_count as usize)
+        .sum();
+    let timestamps = present.iter().map(|&index| region.timestamps()[index]);
+
+    let mut schemes: BTreeMap<String, usize> = BTreeMap::new();
+    for chunk in region.present_chunks() {
+        let scheme = match region.chunk_header(&chunk) {
+            Ok(header) if header.external => format!("{} (external)", header.compression),
+            Ok(header) => header.compression.to_string(),
+            Err(err) => err.kind().to_string(),
+        };
+        *schemes.entry(scheme).or_default() += 1;
+    }
+
+    println!("Region ({}) {}", region.coord(), region.coord().file_name());
+    println!(
+        "  size: {} bytes ({} sectors)",
+        region.len(),
+        region.len().div_ceil(SECTOR_SIZE)
+    );
+    println!(
+        "  chunks: {} ({} sectors allocated)",
+        present.len(),
+        sectors
+    );
+    if let (Some(oldest), Some(newest)) = (timestamps.clone().min(), timestamps.max()) {
+        println!("  last modified: {} to {} (unix time)", oldest, newest);
+    }
+    for (scheme, count) in schemes {
+        println!("  {}: {} chunks", scheme, count);
+    }
+}
+
+fn print_chunk(region: &RegionFile, coord: WorldChunkCoord) {
+    let location = region.location(&coord);
+    if !location.is_present() {
+        println!("Chunk ({}) is not present in the world", coord);
+        return;
+    }
+
+    println!("Chunk ({}) in {}", coord, coord.region_coord().file_name());
+    println!(
+        "  slot {}: sector {}, {} sectors, last modified {} (unix time)",
+        coord.region_index(),
+        location.sector_offset,
+        location.sector_count,
+        region.timestamp(&coord)
+    );
+    if let Ok(header) = region.chunk_header(&coord) {
+        println!(
+            "  compression: {}{}, {} bytes",
+            header.compression,
+            if header.external {
+                " in external .mcc file"
+            } else {
+                ""
+            },
+            header.length
+        );
+    }
+
+    let chunk = match region.read_chunk(&coord) {
+        Ok(chunk) => chunk,
+        Err(err) => {
+            println!("  chunk could not be read: {}", err);
+            return;
+        }
+    };
+
+    match chunk.data_version {
+        Some(data_version) => println!("  data version: {}", data_version),
+        None => println!("  data version: <none, older than 1.9>"),
+    }
+    match &chunk.status {
+        Some(status) if chunk.is_fully_generated() => println!("  status: {}", status),
+        Some(status) => println!("  status: {} (partially generated)", status),
+        None => println!("  status: <none>"),
+    }
+    println!("{} sections", chunk.sections.len());
+
+    for section in &chunk.sections {
+        let base_y = section.y as isize * 16;
+        print!(
+            "  section y={:<3} (Y {} to {})",
+            section.y,
+            base_y,
+            base_y + 15
+        );
+        print!(
+            " block light: {}, sky light: {}",
+            if section.block_light.is_some() {
+                "yes"
+            } else {
+                "no"
+            },
+            if section.sky_light.is_some() {
+                "yes"
+            } else {
+                "no"
+            },
+        );
+        match &section.block_states {
+            None => println!(", no block states"),
+            Some(states) => {
+                println!(", {} palette entries", states.palette.len());
+                for entry in &states.palette {
+                    println!("    {}", entry);
+                }
+            }
+        }
+    }
+}
+
+fn print_block(store: &ChunkStore, coord: WorldBlockCoord) {
+    if !store.chunks.contains_key(&coord.chunk_coord()) {
+        println!(
+            "Chunk ({}) containing block ({}) is not present in the world",
+            coord.chunk_coord(),
+            coord
+        );
+        return;
+    }
+
+    println!("Block ({}) in chunk ({})", coord, coord.chunk_coord());
+    match store.get_block_info_at(&coord) {
+        Some(block) => println!("  state: {}", block.state),
+        None => println!("  state: <no section>"),
+    }
+    match store.get_block_light_at(&coord) {
+        Some(light) => println!("  block light: {}", light),
+        None => println!("  block light: <none>"),
+    }
+    println!("  sky light: {}", store.get_sky_light_at(&coord));
+}
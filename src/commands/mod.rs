@@ -0,0 +1,257 @@
+use std::{collections::BTreeMap, fmt, io, path::PathBuf};
+
+use crate::{
+    blocks::BlockMetadata,
+    chunk_store::ChunkStore,
+    cli::{AreaArgs, WorldArgs},
+    coords::{
+        chunk_bounds::ChunkBounds, region_coord::RegionCoord, world_chunk_coord::WorldChunkCoord,
+    },
+    data_file::DataFileError,
+    region::RegionFile,
+    region_error::RegionError,
+    world::{
+        World, WorldError,
+        dimension::{Dimension, DimensionId},
+    },
+};
+
+pub mod inspect;
+pub mod render;
+pub mod scan;
+pub mod stats;
+
+#[derive(Debug)]
+pub enum CommandError {
+    World(WorldError),
+    /// The world has no region folder for the requested dimension
+    UnknownDimension {
+        requested: DimensionId,
+        available: Vec<DimensionId>,
+    },
+    /// The selected dimension has no region folder in the world
+    MissingRegionDir(PathBuf),
+    /// The output image could not be written
+    Output(PathBuf, image::ImageError),
+    Region(RegionError),
+    /// A block metadata or texture map file could not be loaded
+    DataFile(DataFileError),
+    /// A scan completed but found problems
+    IssuesFound(usize),
+    Io(io::Error),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::World(err) => write!(f, "{}", err),
+            CommandError::UnknownDimension {
+                requested,
+                available,
+            } => {
+                write!(
+                    f,
+                    "dimension {} not found in the world; available:",
+                    requested
+                )?;
+                for id in available {
+                    write!(f, " {}", id)?;
+                }
+                Ok(())
+            }
+            CommandError::MissingRegionDir(path) => write!(
+                f,
+                "region folder {} not found; is this a world directory and does it contain the selected dimension?",
+                path.display()
+            ),
+            CommandError::Output(path, err) => {
+                write!(f, "failed to write {}: {}", path.display(), err)
+            }
+            CommandError::Region(err) => write!(f, "{}", err),
+            CommandError::DataFile(err) => write!(f, "{}", err),
+            CommandError::IssuesFound(count) => write!(f, "scan found {} problems", count),
+            CommandError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<WorldError> for CommandError {
+    fn from(err: WorldError) -> Self {
+        CommandError::World(err)
+    }
+}
+
+impl From<RegionError> for CommandError {
+    fn from(err: RegionError) -> Self {
+        CommandError::Region(err)
+    }
+}
+
+impl From<DataFileError> for CommandError {
+    fn from(err: DataFileError) -> Self {
+        CommandError::DataFile(err)
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(err: io::Error) -> Self {
+        CommandError::Io(err)
+    }
+}
+
+/// Open the world and the dimension selected on the command line
+pub fn open_dimension(args: &WorldArgs) -> Result<(World, Dimension), CommandError> {
+    let world = World::open(&args.world)?;
+    let dimension = world.dimension(&args.dimension).cloned().ok_or_else(|| {
+        CommandError::UnknownDimension {
+            requested: args.dimension.clone(),
+            available: world.dimensions.iter().map(|d| d.id.clone()).collect(),
+        }
+    })?;
+    Ok((world, dimension))
+}
+
+/// Chunks covered by the area arguments, defaulting to every chunk of the
+/// dimension. Returns `None` if the area is empty.
+pub fn resolve_area(
+    args: &AreaArgs,
+    world: &World,
+    dimension: &Dimension,
+) -> Result<Option<ChunkBounds>, CommandError> {
+    let bounds = match args.chunk_bounds() {
+        Some(bounds) => bounds,
+        None => match dimension.chunk_extent()? {
+            Some(extent) => {
+                println!("World extent: chunks ({}) to ({})", extent.min, extent.max);
+                extent
+            }
+            None => return Ok(None),
+        },
+    };
+
+    if !args.clip_to_border {
+        return Ok(Some(bounds));
+    }
+    match world.border(dimension) {
+        Some(border) => Ok(bounds.intersect(&border.chunk_bounds())),
+        None => {
+            println!("No world border found, not clipping");
+            Ok(Some(bounds))
+        }
+    }
+}
+
+/// What went wrong while loading chunks, grouped by reason
+#[derive(Default)]
+pub struct LoadSummary {
+    pub failed_regions: Vec<(RegionCoord, RegionError)>,
+    /// Chunks that could not be read, keyed by `ChunkError::kind`
+    pub failed_chunks: BTreeMap<&'static str, Vec<WorldChunkCoord>>,
+}
+
+impl LoadSummary {
+    pub fn failed_chunk_count(&self) -> usize {
+        self.failed_chunks.values().map(Vec::len).sum()
+    }
+
+    pub fn print(&self) {
+        if self.failed_regions.is_empty() && self.failed_chunks.is_empty() {
+            return;
+        }
+
+        println!(
+            "{} regions and {} chunks could not be read:",
+            self.failed_regions.len(),
+            self.failed_chunk_count()
+        );
+        for (coord, err) in &self.failed_regions {
+            println!("  region {}: {}", coord.file_name(), err);
+        }
+        for (kind, coords) in &self.failed_chunks {
+            println!(
+                "  {}: {} chunks (first at {})",
+                kind,
+                coords.len(),
+                coords[0]
+            );
+        }
+    }
+}
+
+/// Load every chunk of the dimension within `bounds`
+pub fn load_chunks(
+    dimension: &Dimension,
+    bounds: &ChunkBounds,
+    metadata: BlockMetadata,
+) -> Result<(ChunkStore, LoadSummary), CommandError> {
+    let region_dir = dimension.region_dir();
+    if !region_dir.is_dir() {
+        return Err(CommandError::MissingRegionDir(region_dir.to_path_buf()));
+    }
+
+    println!("Loading chunks from ({}) to ({})", bounds.min, bounds.max);
+
+    let region_min = bounds.min.region_coord();
+    let region_max = bounds.max.region_coord();
+
+    let mut store = ChunkStore::new(dimension.id.sky_light(), metadata);
+    let mut summary = LoadSummary::default();
+
+    for rx in region_min.rx..=region_max.rx {
+        for rz in region_min.rz..=region_max.rz {
+            let region_coord = RegionCoord { rx, rz };
+            let path = region_dir.join(region_coord.file_name());
+            if !path.is_file() {
+                println!("Region file {} not found", region_coord.file_name());
+                continue;
+            }
+
+            let region = match RegionFile::open(&path) {
+                Ok(region) => region,
+                Err(err) => {
+                    println!(
+                        "Region file {} unreadable: {}",
+                        region_coord.file_name(),
+                        err
+                    );
+                    summary.failed_regions.push((region_coord, err));
+                    continue;
+                }
+            };
+            let wanted: Vec<WorldChunkCoord> = region
+                .present_chunks()
+                .filter(|coord| bounds.contains(coord))
+                .collect();
+
+            for (coord, chunk) in region.read_chunks(&wanted) {
+                match chunk {
+                    Ok(chunk) => store.insert(coord, chunk),
+                    Err(err) => {
+                        println!("Chunk ({}) could not be read: {}", coord, err);
+                        summary
+                            .failed_chunks
+                            .entry(err.kind())
+                            .or_default()
+                            .push(coord);
+                    }
+                }
+            }
+        }
+    }
+
+    println!("Loaded {} chunks total", store.chunks.len());
+
+    Ok((store, summary))
+}
+
+/// Load a single chunk, returning an empty store if it does not exist
+pub fn load_chunk(
+    dimension: &Dimension,
+    coord: WorldChunkCoord,
+) -> Result<(ChunkStore, LoadSummary), CommandError> {
+    load_chunks(
+        dimension,
+        &ChunkBounds::single(coord),
+        BlockMetadata::bundled(),
+    )
+}
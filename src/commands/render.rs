@@ -0,0 +1,132 @@
+use std::fs;
+
+use crate::{
+    asset_cache::AssetCache,
+    blocks::BlockMetadata,
+    cli::{PartialChunks, RenderArgs},
+    commands::{CommandError, load_chunks, open_dimension, resolve_area},
+    render::renderer::render_world,
+    texture_map::TextureMap,
+    world::dimension::DimensionId,
+};
+
+pub fn run(args: &RenderArgs) -> Result<(), CommandError> {
+    let (world, dimension) = open_dimension(&args.world)?;
+    println!(
+        "World \"{}\", dimension {}",
+        world.level.level_name, dimension.id
+    );
+
+    let Some(bounds) = resolve_area(&args.area, &world, &dimension)? else {
+        println!("No chunks in the selected area, exiting");
+        return Ok(());
+    };
+    let metadata = BlockMetadata::load(args.block_metadata.as_deref())?;
+    let (mut store, summary) = load_chunks(&dimension, &bounds, metadata)?;
+
+    let partial_count = store
+        .chunks
+        .values()
+        .filter(|chunk| !chunk.is_fully_generated())
+        .count();
+    if partial_count > 0 {
+        if args.partial_chunks == PartialChunks::Hide {
+            store.chunks.retain(|_, chunk| chunk.is_fully_generated());
+            println!("Hiding {} partially generated chunks", partial_count);
+        } else {
+            println!("Found {} partially generated chunks", partial_count);
+        }
+    }
+
+    if store.chunks.is_empty() {
+        println!("No chunks loaded, exiting");
+        summary.print();
+        return Ok(());
+    }
+
+    // Get Y range from loaded chunks
+    let (min_y, max_y) = store.get_y_range();
+    println!("Y range across all chunks: {} to {}", min_y, max_y);
+
+    let ceiling = render_ceiling(args.max_y, &dimension.id);
+    if let Some(ceiling) = ceiling {
+        if ceiling < min_y {
+            println!("Nothing to render below Y {}, exiting", ceiling);
+            summary.print();
+            return Ok(());
+        }
+        println!("Cutting away blocks above Y {}", ceiling);
+    }
+    let max_y = cut_above(max_y, ceiling);
+
+    let texture_map = TextureMap::load(args.texture_map.as_deref())?;
+    let asset_cache = AssetCache::new(
+        &args.assets.to_string_lossy(),
+        texture_map,
+        args.animation_frame,
+        args.sprite_size,
+    );
+    println!("Sprite size: {} pixels", asset_cache.sprite_size);
+
+    println!("Rendering chunk region...");
+
+    let img = render_world(
+        &asset_cache,
+        &store,
+        args.render_mode(),
+        &bounds.min,
+        &bounds.max,
+        min_y,
+        max_y,
+    );
+
+    if let Some(parent) = args.output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    img.save(&args.output)
+        .map_err(|err| CommandError::Output(args.output.clone(), err))?;
+    println!(
+        "Rendered world saved to {} ({}x{} pixels)",
+        args.output.display(),
+        img.width(),
+        img.height()
+    );
+    summary.print();
+
+    Ok(())
+}
+
+/// Highest Y to render: `--max-y`, or else the ceiling of the dimension
+fn render_ceiling(max_y: Option<isize>, dimension: &DimensionId) -> Option<isize> {
+    max_y.or(dimension.ceiling_y())
+}
+
+/// The exclusive top `max_y` of the rendered range, lowered so that blocks
+/// above `ceiling` are left out
+fn cut_above(max_y: isize, ceiling: Option<isize>) -> isize {
+    match ceiling {
+        Some(ceiling) => max_y.min(ceiling + 1),
+        None => max_y,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::dimension::NETHER_CEILING_Y;
+
+    #[test]
+    fn blocks_above_the_ceiling_are_cut() {
+        let nether = DimensionId::Nether;
+        assert_eq!(render_ceiling(None, &nether), Some(NETHER_CEILING_Y));
+        assert_eq!(render_ceiling(Some(200), &nether), Some(200));
+        assert_eq!(render_ceiling(Some(64), &DimensionId::Overworld), Some(64));
+        assert_eq!(render_ceiling(None, &DimensionId::End), None);
+
+        // The Nether's sections reach Y 127, its roof is left out
+        assert_eq!(cut_above(128, Some(NETHER_CEILING_Y)), NETHER_CEILING_Y + 1);
+        // A ceiling above the world changes nothing
+        assert_eq!(cut_above(128, Some(200)), 128);
+        assert_eq!(cut_above(320, None), 320);
+    }
+}
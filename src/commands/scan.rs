@@ -0,0 +1,58 @@
+use crate::{
+    cli::ScanArgs, commands::CommandError, region::RegionFile, region_scan::scan_region,
+    world::World,
+};
+
+pub fn run(args: &ScanArgs) -> Result<(), CommandError> {
+    // Scanning must work even when level.dat itself is damaged
+    let mut dimensions = World::discover_dimensions(&args.world);
+    if let Some(requested) = &args.dimension {
+        let available = dimensions.iter().map(|d| d.id.clone()).collect();
+        dimensions.retain(|d| &d.id == requested);
+        if dimensions.is_empty() {
+            return Err(CommandError::UnknownDimension {
+                requested: requested.clone(),
+                available,
+            });
+        }
+    }
+
+    let mut regions = 0;
+    let mut chunks = 0;
+    let mut issues = 0;
+
+    for dimension in dimensions {
+        println!("Scanning {}", dimension.region_dir().display());
+        for path in dimension.region_files()? {
+            regions += 1;
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+            let region = match RegionFile::open(&path) {
+                Ok(region) => region,
+                Err(err) => {
+                    println!("  {}: {}", name, err);
+                    issues += 1;
+                    continue;
+                }
+            };
+
+            chunks += region.present_chunks().count();
+            let region_issues = scan_region(&region);
+            for issue in &region_issues {
+                println!("  {}: {}", name, issue);
+            }
+            issues += region_issues.len();
+        }
+    }
+
+    println!(
+        "Scanned {} regions with {} chunks: {} problems",
+        regions, chunks, issues
+    );
+
+    if issues > 0 {
+        Err(CommandError::IssuesFound(issues))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use crate::{
+    block_registry::BlockId,
+    blocks::BlockMetadata,
+    cli::StatsArgs,
+    commands::{CommandError, load_chunks, open_dimension, resolve_area},
+    coords::chunk_local_block_coord::ChunkLocalBlockCoord,
+};
+
+pub fn run(args: &StatsArgs) -> Result<(), CommandError> {
+    let (world, dimension) = open_dimension(&args.world)?;
+    let Some(bounds) = resolve_area(&args.area, &world, &dimension)? else {
+        println!("No chunks in the selected area, exiting");
+        return Ok(());
+    };
+    let (store, summary) = load_chunks(&dimension, &bounds, BlockMetadata::bundled())?;
+
+    if store.chunks.is_empty() {
+        println!("No chunks loaded, exiting");
+        summary.print();
+        return Ok(());
+    }
+
+    let (min_y, max_y) = store.get_y_range();
+    let section_count: usize = store.chunks.values().map(|c| c.sections.len()).sum();
+
+    let mut id_counts: HashMap<BlockId, u64> = HashMap::new();
+    for chunk in store.chunks.values() {
+        for section in &chunk.sections {
+            for ly in 0..16 {
+                for lz in 0..16 {
+                    for lx in 0..16 {
+                        if let Some(id) = section.block_id_at(ChunkLocalBlockCoord { lx, ly, lz }) {
+                            *id_counts.entry(id).or_default() += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut counts: HashMap<&str, u64> = HashMap::new();
+    for (id, count) in id_counts {
+        *counts
+            .entry(&store.registry.get(id).state.name)
+            .or_default() += count;
+    }
+
+    let total: u64 = counts.values().sum();
+    let mut counts: Vec<(&str, u64)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    println!("Chunks: {}", store.chunks.len());
+    println!("Sections: {}", section_count);
+    println!("Y range: {} to {}", min_y, max_y);
+    println!(
+        "Blocks: {} ({} distinct blocks, {} distinct states)",
+        total,
+        counts.len(),
+        store.registry.len()
+    );
+
+    for (name, count) in counts.iter().take(args.top) {
+        let share = *count as f64 / total as f64 * 100.0;
+        println!("{:>12} {:>6.2}%  {}", count, share, name);
+    }
+    summary.print();
+
+    Ok(())
+}
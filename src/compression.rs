@@ -0,0 +1,178 @@
+use core::fmt;
+use std::io::{self, Read};
+
+use flate2::bufread::{GzDecoder, ZlibDecoder};
+
+/// Set on the compression byte when the chunk is stored in an external `.mcc` file
+pub const EXTERNAL_FLAG: u8 = 0x80;
+
+/// Compression scheme of a chunk, from the byte following its length in the region file
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum CompressionScheme {
+    GZip,
+    Zlib,
+    Uncompressed,
+    /// LZ4 in the `LZ4BlockOutputStream` framing used by Minecraft 1.20.5+
+    Lz4,
+    /// Server-defined compression, named by a string we cannot decode
+    Custom,
+    Unknown(u8),
+}
+
+impl CompressionScheme {
+    /// Scheme and external flag for a compression byte
+    pub fn from_byte(byte: u8) -> (CompressionScheme, bool) {
+        let scheme = match byte & !EXTERNAL_FLAG {
+            1 => CompressionScheme::GZip,
+            2 => CompressionScheme::Zlib,
+            3 => CompressionScheme::Uncompressed,
+            4 => CompressionScheme::Lz4,
+            127 => CompressionScheme::Custom,
+            other => CompressionScheme::Unknown(other),
+        };
+        (scheme, byte & EXTERNAL_FLAG != 0)
+    }
+
+    pub fn is_supported(&self) -> bool {
+        !matches!(
+            self,
+            CompressionScheme::Custom | CompressionScheme::Unknown(_)
+        )
+    }
+
+    /// Decompress a chunk payload
+    pub fn decompress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        match self {
+            CompressionScheme::GZip => {
+                GzDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            CompressionScheme::Zlib => {
+                ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            CompressionScheme::Uncompressed => decompressed.extend_from_slice(data),
+            CompressionScheme::Lz4 => decompressed = decompress_lz4_blocks(data)?,
+            CompressionScheme::Custom | CompressionScheme::Unknown(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("cannot decompress {} chunks", self),
+                ));
+            }
+        }
+        Ok(decompressed)
+    }
+}
+
+impl fmt::Display for CompressionScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionScheme::GZip => write!(f, "gzip"),
+            CompressionScheme::Zlib => write!(f, "zlib"),
+            CompressionScheme::Uncompressed => write!(f, "uncompressed"),
+            CompressionScheme::Lz4 => write!(f, "lz4"),
+            CompressionScheme::Custom => write!(f, "custom"),
+            CompressionScheme::Unknown(byte) => write!(f, "unknown ({})", byte),
+        }
+    }
+}
+
+const LZ4_MAGIC: &[u8] = b"LZ4Block";
+const LZ4_BLOCK_HEADER_SIZE: usize = LZ4_MAGIC.len() + 13;
+const LZ4_METHOD_RAW: u8 = 0x10;
+const LZ4_METHOD_LZ4: u8 = 0x20;
+
+/// Decode a stream written by Java's `LZ4BlockOutputStream`
+///
+/// Each block is the magic, a token (method in the high nibble), then
+/// little-endian compressed length, decompressed length and checksum.
+/// The stream ends with an empty block. Checksums are not verified.
+fn decompress_lz4_blocks(mut data: &[u8]) -> io::Result<Vec<u8>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut result = Vec::new();
+
+    loop {
+        let header = data
+            .get(..LZ4_BLOCK_HEADER_SIZE)
+            .ok_or_else(|| invalid("truncated LZ4 block header".to_string()))?;
+        if &header[..LZ4_MAGIC.len()] != LZ4_MAGIC {
+            return Err(invalid("missing LZ4Block magic".to_string()));
+        }
+
+        let token = header[8];
+        let read_le = |at: usize| {
+            u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+                as usize
+        };
+        let compressed_len = read_le(9);
+        let decompressed_len = read_le(13);
+
+        data = &data[LZ4_BLOCK_HEADER_SIZE..];
+        if decompressed_len == 0 {
+            return Ok(result);
+        }
+
+        let block = data
+            .get(..compressed_len)
+            .ok_or_else(|| invalid(format!("truncated LZ4 block of {} bytes", compressed_len)))?;
+        match token & 0xF0 {
+            LZ4_METHOD_RAW => result.extend_from_slice(block),
+            LZ4_METHOD_LZ4 => {
+                let decoded = lz4_flex::block::decompress(block, decompressed_len)
+                    .map_err(|err| invalid(err.to_string()))?;
+                result.extend_from_slice(&decoded);
+            }
+            method => return Err(invalid(format!("unknown LZ4 block method {:#x}", method))),
+        }
+        data = &data[compressed_len..];
+
+        // Trailing data without an end marker is tolerated
+        if data.is_empty() {
+            return Ok(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lz4_block(method: u8, payload: &[u8], decompressed_len: usize) -> Vec<u8> {
+        let mut block = LZ4_MAGIC.to_vec();
+        block.push(method);
+        block.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        block.extend_from_slice(&(decompressed_len as u32).to_le_bytes());
+        block.extend_from_slice(&0u32.to_le_bytes());
+        block.extend_from_slice(payload);
+        block
+    }
+
+    #[test]
+    fn lz4_block_stream_decodes_all_blocks() {
+        let first = b"hello hello hello hello hello".to_vec();
+        let second = b"raw tail".to_vec();
+
+        let mut stream = lz4_block(
+            LZ4_METHOD_LZ4,
+            &lz4_flex::block::compress(&first),
+            first.len(),
+        );
+        stream.extend(lz4_block(LZ4_METHOD_RAW, &second, second.len()));
+        stream.extend(lz4_block(LZ4_METHOD_RAW, &[], 0));
+
+        let decoded = CompressionScheme::Lz4.decompress(&stream).unwrap();
+        assert_eq!(decoded, [first, second].concat());
+    }
+
+    #[test]
+    fn compression_byte_flags_external_chunks() {
+        assert_eq!(
+            CompressionScheme::from_byte(0x82),
+            (CompressionScheme::Zlib, true)
+        );
+        assert_eq!(
+            CompressionScheme::from_byte(3),
+            (CompressionScheme::Uncompressed, false)
+        );
+        assert!(CompressionScheme::Custom.decompress(&[1, 2, 3]).is_err());
+    }
+}
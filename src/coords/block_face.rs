@@ -1,6 +1,22 @@
-#[derive(Hash, Eq, PartialEq, Clone)]
+#[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
 pub enum BlockFace {
     East,
     South,
     Top,
 }
+
+/// The faces of a block drawn in a sprite. A face is hidden when the
+/// block next to it covers it
+#[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+pub struct VisibleFaces {
+    pub east: bool,
+    pub south: bool,
+    pub top: bool,
+}
+
+impl VisibleFaces {
+    /// Whether the block is covered on every side and can be skipped
+    pub fn is_empty(&self) -> bool {
+        !(self.east || self.south || self.top)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::coords::{world_block_coord::WorldBlockCoord, world_chunk_coord::WorldChunkCoord};
+
+/// Inclusive rectangle of chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkBounds {
+    pub min: WorldChunkCoord,
+    pub max: WorldChunkCoord,
+}
+
+impl ChunkBounds {
+    /// Bounds containing just one chunk
+    pub fn single(coord: WorldChunkCoord) -> Self {
+        Self {
+            min: coord,
+            max: coord,
+        }
+    }
+
+    /// Smallest bounds containing both blocks
+    pub fn from_blocks(a: WorldBlockCoord, b: WorldBlockCoord) -> Self {
+        let (a, b) = (a.chunk_coord(), b.chunk_coord());
+        Self {
+            min: WorldChunkCoord {
+                cx: a.cx.min(b.cx),
+                cz: a.cz.min(b.cz),
+            },
+            max: WorldChunkCoord {
+                cx: a.cx.max(b.cx),
+                cz: a.cz.max(b.cz),
+            },
+        }
+    }
+
+    pub fn contains(&self, coord: &WorldChunkCoord) -> bool {
+        (self.min.cx..=self.max.cx).contains(&coord.cx)
+            && (self.min.cz..=self.max.cz).contains(&coord.cz)
+    }
+
+    /// Grow the bounds to include a chunk
+    pub fn include(&mut self, coord: WorldChunkCoord) {
+        self.min.cx = self.min.cx.min(coord.cx);
+        self.min.cz = self.min.cz.min(coord.cz);
+        self.max.cx = self.max.cx.max(coord.cx);
+        self.max.cz = self.max.cz.max(coord.cz);
+    }
+
+    /// Chunks inside both bounds, or `None` if they do not overlap
+    pub fn intersect(&self, other: &ChunkBounds) -> Option<ChunkBounds> {
+        let bounds = ChunkBounds {
+            min: WorldChunkCoord {
+                cx: self.min.cx.max(other.min.cx),
+                cz: self.min.cz.max(other.min.cz),
+            },
+            max: WorldChunkCoord {
+                cx: self.max.cx.min(other.max.cx),
+                cz: self.max.cz.min(other.max.cz),
+            },
+        };
+        (bounds.min.cx <= bounds.max.cx && bounds.min.cz <= bounds.max.cz).then_some(bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(min_cx: isize, min_cz: isize, max_cx: isize, max_cz: isize) -> ChunkBounds {
+        ChunkBounds {
+            min: WorldChunkCoord {
+                cx: min_cx,
+                cz: min_cz,
+            },
+            max: WorldChunkCoord {
+                cx: max_cx,
+                cz: max_cz,
+            },
+        }
+    }
+
+    #[test]
+    fn bounds_grow_and_intersect() {
+        let mut grown = ChunkBounds::single(WorldChunkCoord { cx: 0, cz: 0 });
+        grown.include(WorldChunkCoord { cx: -3, cz: 5 });
+        assert_eq!(grown, bounds(-3, 0, 0, 5));
+
+        assert_eq!(
+            grown.intersect(&bounds(-1, -10, 10, 2)),
+            Some(bounds(-1, 0, 0, 2))
+        );
+        assert_eq!(grown.intersect(&bounds(1, 0, 4, 4)), None);
+    }
+}
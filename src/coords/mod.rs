@@ -1,4 +1,5 @@
 pub mod block_face;
+pub mod chunk_bounds;
 pub mod chunk_local_block_coord;
 pub mod constants;
 pub mod painters_range;
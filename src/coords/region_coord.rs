@@ -1,3 +1,10 @@
+use core::fmt;
+
+use crate::coords::world_chunk_coord::WorldChunkCoord;
+
+/// Number of chunks along each side of a region
+pub const REGION_SIZE: isize = 32;
+
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 pub struct RegionCoord {
     pub rx: isize,
@@ -8,4 +15,29 @@ impl RegionCoord {
     pub fn file_name(&self) -> String {
         format!("r.{}.{}.mca", self.rx, self.rz)
     }
+
+    /// Parse a region file name like `r.-1.2.mca`
+    pub fn from_file_name(name: &str) -> Option<RegionCoord> {
+        let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+        let rx = parts.next()?.parse().ok()?;
+        let rz = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(RegionCoord { rx, rz })
+    }
+
+    /// World coordinate of the chunk at a region-local slot
+    pub fn chunk_coord(&self, local_x: usize, local_z: usize) -> WorldChunkCoord {
+        WorldChunkCoord {
+            cx: self.rx * REGION_SIZE + local_x as isize,
+            cz: self.rz * REGION_SIZE + local_z as isize,
+        }
+    }
+}
+
+impl fmt::Display for RegionCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.rx, self.rz)
+    }
 }
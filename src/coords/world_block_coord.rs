@@ -36,6 +36,14 @@ impl WorldBlockCoord {
         }
     }
 
+    pub fn bottom_neg_y(&self) -> WorldBlockCoord {
+        WorldBlockCoord {
+            x: self.x,
+            y: self.y - 1,
+            z: self.z,
+        }
+    }
+
     pub fn south_pos_z(&self) -> WorldBlockCoord {
         WorldBlockCoord {
             x: self.x,
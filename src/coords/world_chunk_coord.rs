@@ -1,7 +1,9 @@
 use core::fmt;
 
 use crate::coords::{
-    constants::MC_CHUNK_SIZE, painters_range::PaintersRange, region_coord::RegionCoord,
+    constants::MC_CHUNK_SIZE,
+    painters_range::PaintersRange,
+    region_coord::{REGION_SIZE, RegionCoord},
     world_block_coord::WorldBlockCoord,
 };
 
@@ -14,25 +16,21 @@ pub struct WorldChunkCoord {
 impl WorldChunkCoord {
     pub fn region_coord(&self) -> RegionCoord {
         RegionCoord {
-            rx: self.cx.div_euclid(32),
-            rz: self.cz.div_euclid(32),
+            rx: self.cx.div_euclid(REGION_SIZE),
+            rz: self.cz.div_euclid(REGION_SIZE),
         }
     }
 
-    pub fn range_to(self, other: &WorldChunkCoord) -> WorldChunkCoordIterator {
-        let min_coord = WorldChunkCoord {
-            cx: self.cx.min(other.cx),
-            cz: self.cz.min(other.cz),
-        };
-        let max_coord = WorldChunkCoord {
-            cx: self.cx.max(other.cx),
-            cz: self.cz.max(other.cz),
-        };
-        WorldChunkCoordIterator {
-            current: min_coord,
-            min: min_coord,
-            max: max_coord,
-        }
+    /// Name of the file holding this chunk when it is too large for its region
+    pub fn external_file_name(&self) -> String {
+        format!("c.{}.{}.mcc", self.cx, self.cz)
+    }
+
+    /// Index of this chunk in its region's location and timestamp tables
+    pub fn region_index(&self) -> usize {
+        let local_x = self.cx.rem_euclid(REGION_SIZE);
+        let local_z = self.cz.rem_euclid(REGION_SIZE);
+        (local_x + local_z * REGION_SIZE) as usize
     }
 
     pub fn world_block_coord_min(&self, min_y: isize) -> WorldBlockCoord {
@@ -58,29 +56,6 @@ impl fmt::Display for WorldChunkCoord {
     }
 }
 
-pub struct WorldChunkCoordIterator {
-    current: WorldChunkCoord,
-    min: WorldChunkCoord,
-    max: WorldChunkCoord,
-}
-
-impl Iterator for WorldChunkCoordIterator {
-    type Item = WorldChunkCoord;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current.cx > self.max.cx {
-            return None;
-        }
-        let res = self.current;
-        self.current.cz += 1;
-        if self.current.cz > self.max.cz {
-            self.current.cz = self.min.cz;
-            self.current.cx += 1;
-        }
-        Some(res)
-    }
-}
-
 pub struct WorldChunkCoordPaintersIterator {
     /// The iterator is complete
     exhausted: bool,
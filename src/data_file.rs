@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+/// A user data file that could not be loaded
+#[derive(Debug)]
+pub enum DataFileError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for DataFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataFileError::Io(path, err) => {
+                write!(f, "failed to read {}: {}", path.display(), err)
+            }
+            DataFileError::Parse(path, err) => {
+                write!(f, "invalid data in {}: {}", path.display(), err)
+            }
+        }
+    }
+}
+
+/// Read and parse a JSON data file
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, DataFileError> {
+    let text = fs::read_to_string(path).map_err(|err| DataFileError::Io(path.into(), err))?;
+    serde_json::from_str(&text).map_err(|err| DataFileError::Parse(path.into(), err))
+}
+
+/// Matches block names by prefix and suffix, both optional
+#[derive(Deserialize, Debug)]
+pub struct NamePattern {
+    prefix: Option<String>,
+    suffix: Option<String>,
+}
+
+impl NamePattern {
+    /// The part of `name` between the prefix and the suffix, if it matches
+    pub fn strip<'a>(&self, name: &'a str) -> Option<&'a str> {
+        let name = match &self.prefix {
+            Some(prefix) => name.strip_prefix(prefix.as_str())?,
+            None => name,
+        };
+        match &self.suffix {
+            Some(suffix) => name.strip_suffix(suffix.as_str()),
+            None => Some(name),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.strip(name).is_some()
+    }
+}
@@ -0,0 +1,138 @@
+use fastnbt::LongArray;
+use serde::Deserialize;
+
+use crate::section::{BitPacking, PackingError, unpack_values};
+
+const COLUMN_COUNT: usize = 256; // 16 * 16
+
+/// The `Heightmaps` compound of a chunk, saved since 1.13. Each value is the
+/// height of the first free block above a column, counted from the
+/// chunk's lowest Y
+#[derive(Deserialize, Debug)]
+pub struct Heightmaps {
+    /// Highest non-air block
+    #[serde(rename = "WORLD_SURFACE")]
+    pub world_surface: Option<LongArray>,
+    /// Highest block that blocks motion or holds a fluid, like water and leaves
+    #[serde(rename = "MOTION_BLOCKING")]
+    pub motion_blocking: Option<LongArray>,
+    /// Like `MOTION_BLOCKING`, ignoring leaves
+    #[serde(rename = "MOTION_BLOCKING_NO_LEAVES")]
+    pub motion_blocking_no_leaves: Option<LongArray>,
+    /// Highest block that blocks motion, ignoring fluids
+    #[serde(rename = "OCEAN_FLOOR")]
+    pub ocean_floor: Option<LongArray>,
+    #[serde(default)]
+    pub unpacked_surface: Option<Vec<u16>>,
+    #[serde(default)]
+    pub unpacked_floor: Option<Vec<u16>>,
+}
+
+impl Heightmaps {
+    /// Unpack the surface and the floor. The floor is the lower of the ocean
+    /// floor and motion blocking without leaves, so neither the sea bed nor
+    /// the ground under trees is hidden, falling back to motion blocking
+    pub fn ensure_unpacked(&mut self, packing: BitPacking) -> Result<(), PackingError> {
+        if let Some(surface) = &self.world_surface
+            && self.unpacked_surface.is_none()
+        {
+            self.unpacked_surface = Some(unpack_heights(surface, packing)?);
+        }
+        if self.unpacked_floor.is_some() {
+            return Ok(());
+        }
+
+        let mut floor: Option<Vec<u16>> = None;
+        for heights in [&self.ocean_floor, &self.motion_blocking_no_leaves]
+            .into_iter()
+            .flatten()
+        {
+            let heights = unpack_heights(heights, packing)?;
+            floor = Some(match floor {
+                Some(floor) => floor.iter().zip(heights).map(|(&a, b)| a.min(b)).collect(),
+                None => heights,
+            });
+        }
+        if floor.is_none()
+            && let Some(heights) = &self.motion_blocking
+        {
+            floor = Some(unpack_heights(heights, packing)?);
+        }
+        self.unpacked_floor = floor;
+        Ok(())
+    }
+
+    /// Y of the highest non-air block of column `index` (`z * 16 + x`)
+    pub fn surface_y(&self, index: usize, min_y: isize) -> Option<isize> {
+        let heights = self.unpacked_surface.as_ref()?;
+        Some(min_y + heights[index] as isize - 1)
+    }
+
+    /// Y of the highest solid block of column `index`. Everything below it
+    /// is covered from above
+    pub fn floor_y(&self, index: usize, min_y: isize) -> Option<isize> {
+        let heights = self.unpacked_floor.as_ref()?;
+        Some(min_y + heights[index] as isize - 1)
+    }
+}
+
+/// The bits per height depend on the world height, which the chunk does not
+/// store. Take the smallest width that packs into exactly this many longs
+fn unpack_heights(data: &LongArray, packing: BitPacking) -> Result<Vec<u16>, PackingError> {
+    let bits_per_value = (1..=16)
+        .find(|&bits| packing.long_count(bits, COLUMN_COUNT) == data.len())
+        .ok_or(PackingError::TooShort {
+            len: data.len(),
+            expected: packing.long_count(9, COLUMN_COUNT),
+        })?;
+    unpack_values(data, packing, bits_per_value, COLUMN_COUNT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heightmaps_unpack_nine_bit_heights() {
+        // 384 blocks tall: 9 bits, 7 per long with 1 bit of padding
+        let mut data = vec![0i64; 37];
+        data[0] = 200 | (1 << 63);
+        data[1] = 65 << 9;
+        let mut heightmaps = Heightmaps {
+            world_surface: Some(LongArray::new(data)),
+            motion_blocking: None,
+            motion_blocking_no_leaves: None,
+            ocean_floor: None,
+            unpacked_surface: None,
+            unpacked_floor: None,
+        };
+        heightmaps.ensure_unpacked(BitPacking::Aligned).unwrap();
+
+        assert_eq!(heightmaps.surface_y(0, -64), Some(135));
+        assert_eq!(heightmaps.surface_y(8, -64), Some(0));
+        assert_eq!(heightmaps.surface_y(1, -64), Some(-65));
+        assert_eq!(heightmaps.floor_y(0, -64), None);
+    }
+
+    #[test]
+    fn heightmap_floor_ignores_water_and_leaves() {
+        let heights = |first: i64| {
+            let mut data = vec![0i64; 37];
+            data[0] = first;
+            Some(LongArray::new(data))
+        };
+        let mut heightmaps = Heightmaps {
+            world_surface: heights(120),
+            // Leaves at 119 over ground at 100, water at 80 over a sea bed at 70
+            motion_blocking: heights(120 | (81 << 9)),
+            motion_blocking_no_leaves: heights(101 | (81 << 9)),
+            ocean_floor: heights(120 | (71 << 9)),
+            unpacked_surface: None,
+            unpacked_floor: None,
+        };
+        heightmaps.ensure_unpacked(BitPacking::Aligned).unwrap();
+
+        assert_eq!(heightmaps.floor_y(0, 0), Some(100));
+        assert_eq!(heightmaps.floor_y(1, 0), Some(70));
+    }
+}
@@ -0,0 +1,61 @@
+/// Name of a numeric biome ID from before 1.18. Biomes that were merged or
+/// renamed since map to their current name, unknown IDs to plains
+pub fn biome_name(id: u32) -> &'static str {
+    match id {
+        0 => "minecraft:ocean",
+        2 | 17 | 130 => "minecraft:desert",
+        3 | 20 => "minecraft:windswept_hills",
+        4 | 18 => "minecraft:forest",
+        5 | 19 | 133 => "minecraft:taiga",
+        6 | 134 => "minecraft:swamp",
+        7 => "minecraft:river",
+        8 => "minecraft:nether_wastes",
+        9 => "minecraft:the_end",
+        10 => "minecraft:frozen_ocean",
+        11 => "minecraft:frozen_river",
+        12 | 13 => "minecraft:snowy_plains",
+        14 | 15 => "minecraft:mushroom_fields",
+        16 => "minecraft:beach",
+        21 | 22 | 149 => "minecraft:jungle",
+        23 | 151 => "minecraft:sparse_jungle",
+        24 => "minecraft:deep_ocean",
+        25 => "minecraft:stony_shore",
+        26 => "minecraft:snowy_beach",
+        27 | 28 => "minecraft:birch_forest",
+        29 | 157 => "minecraft:dark_forest",
+        30 | 31 | 158 => "minecraft:snowy_taiga",
+        32 | 33 => "minecraft:old_growth_pine_taiga",
+        34 => "minecraft:windswept_forest",
+        35 => "minecraft:savanna",
+        36 => "minecraft:savanna_plateau",
+        37 | 39 | 167 => "minecraft:badlands",
+        38 | 166 => "minecraft:wooded_badlands",
+        40 => "minecraft:small_end_islands",
+        41 => "minecraft:end_midlands",
+        42 => "minecraft:end_highlands",
+        43 => "minecraft:end_barrens",
+        44 | 47 => "minecraft:warm_ocean",
+        45 => "minecraft:lukewarm_ocean",
+        46 => "minecraft:cold_ocean",
+        48 => "minecraft:deep_lukewarm_ocean",
+        49 => "minecraft:deep_cold_ocean",
+        50 => "minecraft:deep_frozen_ocean",
+        127 => "minecraft:the_void",
+        129 => "minecraft:sunflower_plains",
+        131 | 162 => "minecraft:windswept_gravelly_hills",
+        132 => "minecraft:flower_forest",
+        140 => "minecraft:ice_spikes",
+        155 | 156 => "minecraft:old_growth_birch_forest",
+        160 | 161 => "minecraft:old_growth_spruce_taiga",
+        163 | 164 => "minecraft:windswept_savanna",
+        165 => "minecraft:eroded_badlands",
+        168 | 169 => "minecraft:bamboo_jungle",
+        170 => "minecraft:soul_sand_valley",
+        171 => "minecraft:crimson_forest",
+        172 => "minecraft:warped_forest",
+        173 => "minecraft:basalt_deltas",
+        174 => "minecraft:dripstone_caves",
+        175 => "minecraft:lush_caves",
+        _ => "minecraft:plains",
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::BTreeMap;
+
+use crate::section::PaletteEntry;
+
+const WOODS: [&str; 6] = ["oak", "spruce", "birch", "jungle", "acacia", "dark_oak"];
+
+const COLORS: [&str; 16] = [
+    "white",
+    "orange",
+    "magenta",
+    "light_blue",
+    "yellow",
+    "lime",
+    "pink",
+    "gray",
+    "light_gray",
+    "cyan",
+    "purple",
+    "blue",
+    "brown",
+    "green",
+    "red",
+    "black",
+];
+
+const AGES: [&str; 16] = [
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15",
+];
+
+/// Slabs of the stone slab IDs 43 and 44, by data value
+const STONE_SLABS: [&str; 8] = [
+    "smooth_stone",
+    "sandstone",
+    "petrified_oak",
+    "cobblestone",
+    "brick",
+    "stone_brick",
+    "nether_brick",
+    "quartz",
+];
+
+/// Entry of `names` for a data value, or the first one for unknown values
+fn pick(names: &[&'static str], data: u8) -> &'static str {
+    names.get(data as usize).copied().unwrap_or(names[0])
+}
+
+/// Axis of logs and pillars, stored in the upper two data bits
+fn log_axis(data: u8) -> &'static str {
+    match data >> 2 {
+        1 => "x",
+        2 => "z",
+        _ => "y",
+    }
+}
+
+fn half(data: u8) -> &'static str {
+    if data & 8 != 0 { "upper" } else { "lower" }
+}
+
+/// Translate a pre-1.13 numeric block ID and data value into the block
+/// state that replaced it. Unknown IDs, like those of mods, become air
+pub fn flatten(id: u16, data: u8) -> PaletteEntry {
+    let wood = pick(&WOODS, data & 7);
+    let color = COLORS[(data & 15) as usize];
+    let mut properties: Vec<(&str, &str)> = Vec::new();
+
+    let name: String = match id {
+        1 => pick(
+            &[
+                "stone",
+                "granite",
+                "polished_granite",
+                "diorite",
+                "polished_diorite",
+                "andesite",
+                "polished_andesite",
+            ],
+            data,
+        )
+        .into(),
+        2 => "grass_block".into(),
+        3 => pick(&["dirt", "coarse_dirt", "podzol"], data).into(),
+        4 => "cobblestone".into(),
+        5 => format!("{}_planks", wood),
+        6 => format!("{}_sapling", wood),
+        7 => "bedrock".into(),
+        8 | 9 => "water".into(),
+        10 | 11 => "lava".into(),
+        12 => pick(&["sand", "red_sand"], data).into(),
+        13 => "gravel".into(),
+        14 => "gold_ore".into(),
+        15 => "iron_ore".into(),
+        16 => "coal_ore".into(),
+        17 | 162 => {
+            let wood = if id == 17 {
+                pick(&WOODS[..4], data & 3)
+            } else {
+                pick(&WOODS[4..], data & 3)
+            };
+            if data >> 2 == 3 {
+                format!("{}_wood", wood)
+            } else {
+                properties.push(("axis", log_axis(data)));
+                format!("{}_log", wood)
+            }
+        }
+        18 => format!("{}_leaves", pick(&WOODS[..4], data & 3)),
+        161 => format!("{}_leaves", pick(&WOODS[4..], data & 3)),
+        19 => pick(&["sponge", "wet_sponge"], data).into(),
+        20 => "glass".into(),
+        21 => "lapis_ore".into(),
+        22 => "lapis_block".into(),
+        23 => "dispenser".into(),
+        24 => pick(&["sandstone", "chiseled_sandstone", "cut_sandstone"], data).into(),
+        25 => "note_block".into(),
+        26 => "red_bed".into(),
+        27 => "powered_rail".into(),
+        28 => "detector_rail".into(),
+        29 => "sticky_piston".into(),
+        30 => "cobweb".into(),
+        31 => pick(&["dead_bush", "short_grass", "fern"], data).into(),
+        32 => "dead_bush".into(),
+        33 => "piston".into(),
+        34 => "piston_head".into(),
+        35 => format!("{}_wool", color),
+        37 => "dandelion".into(),
+        38 => pick(
+            &[
+                "poppy",
+                "blue_orchid",
+                "allium",
+                "azure_bluet",
+                "red_tulip",
+                "orange_tulip",
+                "white_tulip",
+                "pink_tulip",
+                "oxeye_daisy",
+            ],
+            data,
+        )
+        .into(),
+        39 => "brown_mushroom".into(),
+        40 => "red_mushroom".into(),
+        41 => "gold_block".into(),
+        42 => "iron_block".into(),
+        43 | 44 => {
+            let slab_type = match (id, data & 8) {
+                (43, _) => "double",
+                (_, 0) => "bottom",
+                _ => "top",
+            };
+            properties.push(("type", slab_type));
+            format!("{}_slab", pick(&STONE_SLABS, data & 7))
+        }
+        45 => "bricks".into(),
+        46 => "tnt".into(),
+        47 => "bookshelf".into(),
+        48 => "mossy_cobblestone".into(),
+        49 => "obsidian".into(),
+        50 => "torch".into(),
+        51 => "fire".into(),
+        52 => "spawner".into(),
+        53 => "oak_stairs".into(),
+        54 => "chest".into(),
+        55 => "redstone_wire".into(),
+        56 => "diamond_ore".into(),
+        57 => "diamond_block".into(),
+        58 => "crafting_table".into(),
+        59 => {
+            properties.push(("age", AGES[(data & 7) as usize]));
+            "wheat".into()
+        }
+        60 => "farmland".into(),
+        61 | 62 => {
+            properties.push(("lit", if id == 62 { "true" } else { "false" }));
+            "furnace".into()
+        }
+        63 => "oak_sign".into(),
+        64 | 71 | 193..=197 => {
+            properties.push(("half", half(data)));
+            match id {
+                64 => "oak_door",
+                71 => "iron_door",
+                193 => "spruce_door",
+                194 => "birch_door",
+                195 => "jungle_door",
+                196 => "acacia_door",
+                _ => "dark_oak_door",
+            }
+            .into()
+        }
+        65 => "ladder".into(),
+        66 => "rail".into(),
+        67 => "cobblestone_stairs".into(),
+        68 => "oak_wall_sign".into(),
+        69 => "lever".into(),
+        70 => "stone_pressure_plate".into(),
+        72 => "oak_pressure_plate".into(),
+        73 | 74 => {
+            properties.push(("lit", if id == 74 { "true" } else { "false" }));
+            "redstone_ore".into()
+        }
+        75 | 76 => "redstone_torch".into(),
+        77 => "stone_button".into(),
+        78 => "snow".into(),
+        79 => "ice".into(),
+        80 => "snow_block".into(),
+        81 => "cactus".into(),
+        82 => "clay".into(),
+        83 => "sugar_cane".into(),
+        84 => "jukebox".into(),
+        85 => "oak_fence".into(),
+        86 => "carved_pumpkin".into(),
+        87 => "netherrack".into(),
+        88 => "soul_sand".into(),
+        89 => "glowstone".into(),
+        90 => "nether_portal".into(),
+        91 => "jack_o_lantern".into(),
+        92 => "cake".into(),
+        93 | 94 => "repeater".into(),
+        95 => format!("{}_stained_glass", color),
+        96 => "oak_trapdoor".into(),
+        97 => pick(
+            &[
+                "infested_stone",
+                "infested_cobblestone",
+                "infested_stone_bricks",
+                "infested_mossy_stone_bricks",
+                "infested_cracked_stone_bricks",
+                "infested_chiseled_stone_bricks",
+            ],
+            data,
+        )
+        .into(),
+        98 => pick(
+            &[
+                "stone_bricks",
+                "mossy_stone_bricks",
+                "cracked_stone_bricks",
+                "chiseled_stone_bricks",
+            ],
+            data,
+        )
+        .into(),
+        99 | 100 if data == 10 || data == 15 => "mushroom_stem".into(),
+        99 => "brown_mushroom_block".into(),
+        100 => "red_mushroom_block".into(),
+        101 => "iron_bars".into(),
+        102 => "glass_pane".into(),
+        103 => "melon".into(),
+        104 => "pumpkin_stem".into(),
+        105 => "melon_stem".into(),
+        106 => "vine".into(),
+        107 => "oak_fence_gate".into(),
+        108 => "brick_stairs".into(),
+        109 => "stone_brick_stairs".into(),
+        110 => "mycelium".into(),
+        111 => "lily_pad".into(),
+        112 => "nether_bricks".into(),
+        113 => "nether_brick_fence".into(),
+        114 => "nether_brick_stairs".into(),
+        115 => {
+            properties.push(("age", AGES[(data & 3) as usize]));
+            "nether_wart".into()
+        }
+        116 => "enchanting_table".into(),
+        117 => "brewing_stand".into(),
+        118 => "cauldron".into(),
+        119 => "end_portal".into(),
+        120 => "end_portal_frame".into(),
+        121 => "end_stone".into(),
+        122 => "dragon_egg".into(),
+        123 | 124 => {
+            properties.push(("lit", if id == 124 { "true" } else { "false" }));
+            "redstone_lamp".into()
+        }
+        125 | 126 => {
+            let slab_type = match (id, data & 8) {
+                (125, _) => "double",
+                (_, 0) => "bottom",
+                _ => "top",
+            };
+            properties.push(("type", slab_type));
+            format!("{}_slab", wood)
+        }
+        127 => {
+            properties.push(("age", AGES[(data >> 2) as usize]));
+            "cocoa".into()
+        }
+        128 => "sandstone_stairs".into(),
+        129 => "emerald_ore".into(),
+        130 => "ender_chest".into(),
+        131 => "tripwire_hook".into(),
+        132 => "tripwire".into(),
+        133 => "emerald_block".into(),
+        134 => "spruce_stairs".into(),
+        135 => "birch_stairs".into(),
+        136 => "jungle_stairs".into(),
+        137 => "command_block".into(),
+        138 => "beacon".into(),
+        139 => pick(&["cobblestone_wall", "mossy_cobblestone_wall"], data).into(),
+        140 => "flower_pot".into(),
+        141 | 142 => {
+            properties.push(("age", AGES[(data & 7) as usize]));
+            if id == 141 { "carrots" } else { "potatoes" }.into()
+        }
+        143 => "oak_button".into(),
+        144 => "skeleton_skull".into(),
+        145 => "anvil".into(),
+        146 => "trapped_chest".into(),
+        147 => "light_weighted_pressure_plate".into(),
+        148 => "heavy_weighted_pressure_plate".into(),
+        149 | 150 => "comparator".into(),
+        151 | 178 => "daylight_detector".into(),
+        152 => "redstone_block".into(),
+        153 => "nether_quartz_ore".into(),
+        154 => "hopper".into(),
+        155 => match data {
+            1 => "chiseled_quartz_block".into(),
+            2..=4 => {
+                properties.push(("axis", ["y", "x", "z"][(data - 2) as usize]));
+                "quartz_pillar".into()
+            }
+            _ => "quartz_block".into(),
+        },
+        156 => "quartz_stairs".into(),
+        157 => "activator_rail".into(),
+        158 => "dropper".into(),
+        159 => format!("{}_terracotta", color),
+        160 => format!("{}_stained_glass_pane", color),
+        163 => "acacia_stairs".into(),
+        164 => "dark_oak_stairs".into(),
+        165 => "slime_block".into(),
+        166 => "barrier".into(),
+        167 => "iron_trapdoor".into(),
+        168 => pick(
+            &["prismarine", "prismarine_bricks", "dark_prismarine"],
+            data,
+        )
+        .into(),
+        169 => "sea_lantern".into(),
+        170 => {
+            properties.push(("axis", log_axis(data)));
+            "hay_block".into()
+        }
+        171 => format!("{}_carpet", color),
+        172 => "terracotta".into(),
+        173 => "coal_block".into(),
+        174 => "packed_ice".into(),
+        175 => {
+            properties.push(("half", half(data)));
+            pick(
+                &[
+                    "sunflower",
+                    "lilac",
+                    "tall_grass",
+                    "large_fern",
+                    "rose_bush",
+                    "peony",
+                ],
+                data & 7,
+            )
+            .into()
+        }
+        176 => "white_banner".into(),
+        177 => "white_wall_banner".into(),
+        179 => pick(
+            &[
+                "red_sandstone",
+                "chiseled_red_sandstone",
+                "cut_red_sandstone",
+            ],
+            data,
+        )
+        .into(),
+        180 => "red_sandstone_stairs".into(),
+        181 | 182 => {
+            let slab_type = match (id, data & 8) {
+                (181, _) => "double",
+                (_, 0) => "bottom",
+                _ => "top",
+            };
+            properties.push(("type", slab_type));
+            "red_sandstone_slab".into()
+        }
+        183 => "spruce_fence_gate".into(),
+        184 => "birch_fence_gate".into(),
+        185 => "jungle_fence_gate".into(),
+        186 => "dark_oak_fence_gate".into(),
+        187 => "acacia_fence_gate".into(),
+        188 => "spruce_fence".into(),
+        189 => "birch_fence".into(),
+        190 => "jungle_fence".into(),
+        191 => "dark_oak_fence".into(),
+        192 => "acacia_fence".into(),
+        198 => "end_rod".into(),
+        199 => "chorus_plant".into(),
+        200 => "chorus_flower".into(),
+        201 => "purpur_block".into(),
+        202 | 216 => {
+            properties.push(("axis", log_axis(data)));
+            if id == 202 {
+                "purpur_pillar"
+            } else {
+                "bone_block"
+            }
+            .into()
+        }
+        203 => "purpur_stairs".into(),
+        204 | 205 => {
+            let slab_type = match (id, data & 8) {
+                (204, _) => "double",
+                (_, 0) => "bottom",
+                _ => "top",
+            };
+            properties.push(("type", slab_type));
+            "purpur_slab".into()
+        }
+        206 => "end_stone_bricks".into(),
+        207 => {
+            properties.push(("age", AGES[(data & 3) as usize]));
+            "beetroots".into()
+        }
+        208 => "dirt_path".into(),
+        209 => "end_gateway".into(),
+        210 => "repeating_command_block".into(),
+        211 => "chain_command_block".into(),
+        212 => "frosted_ice".into(),
+        213 => "magma_block".into(),
+        214 => "nether_wart_block".into(),
+        215 => "red_nether_bricks".into(),
+        217 => "structure_void".into(),
+        218 => "observer".into(),
+        219..=234 => format!("{}_shulker_box", COLORS[(id - 219) as usize]),
+        235..=250 => format!("{}_glazed_terracotta", COLORS[(id - 235) as usize]),
+        251 => format!("{}_concrete", color),
+        252 => format!("{}_concrete_powder", color),
+        255 => "structure_block".into(),
+        _ => "air".into(),
+    };
+
+    PaletteEntry {
+        name: format!("minecraft:{}", name),
+        properties: properties
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+    }
+}
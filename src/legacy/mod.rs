@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+
+use fastnbt::{ByteArray, LongArray, Value};
+use serde::Deserialize;
+
+use crate::chunk::Chunk;
+use crate::heightmap::Heightmaps;
+use crate::section::{Biomes, BlockStates, PaletteEntry, Section};
+
+pub mod biome_ids;
+pub mod block_ids;
+
+/// First data version (17w47a) with block state palettes instead of
+/// numeric block IDs
+pub const FLATTENING_VERSION: i32 = 1451;
+
+/// The `Level` compound of chunks older than 21w43a
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LegacyLevel {
+    #[serde(rename = "xPos")]
+    x_pos: i32,
+    #[serde(rename = "zPos")]
+    z_pos: i32,
+    /// Since 1.13
+    status: Option<String>,
+    #[serde(default)]
+    sections: Vec<LegacySection>,
+    /// Numeric biome IDs: 256 columns before 19w36a, 1024 4x4x4 cells after
+    biomes: Option<Value>,
+    /// Since 1.13
+    heightmaps: Option<Heightmaps>,
+}
+
+/// A section of a chunk older than 21w43a
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LegacySection {
+    y: i8,
+    // 1.13 to 1.17
+    palette: Option<Vec<PaletteEntry>>,
+    block_states: Option<LongArray>,
+    // Before 1.13: block IDs, their extra high bits and data values
+    blocks: Option<ByteArray>,
+    add: Option<ByteArray>,
+    data: Option<ByteArray>,
+    block_light: Option<ByteArray>,
+    sky_light: Option<ByteArray>,
+}
+
+/// Convert a pre-21w43a chunk into the current layout
+pub fn convert_level(level: LegacyLevel, data_version: Option<i32>) -> Chunk {
+    let flattened = data_version.is_some_and(|version| version >= FLATTENING_VERSION);
+    let biome_ids = level.biomes.as_ref().and_then(biome_id_array);
+
+    // Numeric sections are converted first, as tall plants look at the
+    // section below them
+    let numeric: Vec<Option<BlockStates>> = level
+        .sections
+        .iter()
+        .map(|section| {
+            if flattened {
+                return None;
+            }
+            let below = level
+                .sections
+                .iter()
+                .find(|other| Some(other.y) == section.y.checked_sub(1))
+                .and_then(NumericBlocks::of);
+            Some(numeric_block_states(NumericBlocks::of(section)?, below))
+        })
+        .collect();
+
+    let sections = level
+        .sections
+        .into_iter()
+        .zip(numeric)
+        .map(|(section, numeric)| {
+            let y = section.y;
+            let biomes = biome_ids.as_ref().map(|ids| section_biomes(ids, y));
+            let block_states = if flattened {
+                section.palette.map(|palette| BlockStates {
+                    palette,
+                    data: section.block_states,
+                    unpacked_data: None,
+                    palette_ids: Vec::new(),
+                })
+            } else {
+                numeric
+            };
+            Section {
+                y,
+                block_states,
+                biomes,
+                block_light: section.block_light,
+                sky_light: section.sky_light,
+            }
+        })
+        .collect();
+
+    Chunk {
+        data_version,
+        x_pos: level.x_pos,
+        z_pos: level.z_pos,
+        y_pos: None,
+        status: level.status,
+        sections,
+        heightmaps: level.heightmaps,
+    }
+}
+
+/// Read a nibble out of a 4096 entry nibble array, low nibble first
+fn nibble(array: &ByteArray, index: usize) -> u8 {
+    let byte = array.get(index / 2).copied().unwrap_or(0) as u8;
+    if index.is_multiple_of(2) {
+        byte & 0x0F
+    } else {
+        byte >> 4
+    }
+}
+
+/// Block IDs and data values of a section from before 1.13
+#[derive(Clone, Copy)]
+struct NumericBlocks<'a> {
+    blocks: &'a ByteArray,
+    add: Option<&'a ByteArray>,
+    data: Option<&'a ByteArray>,
+}
+
+impl<'a> NumericBlocks<'a> {
+    fn of(section: &'a LegacySection) -> Option<Self> {
+        Some(Self {
+            blocks: section.blocks.as_ref()?,
+            add: section.add.as_ref(),
+            data: section.data.as_ref(),
+        })
+    }
+
+    fn id_at(&self, index: usize) -> u16 {
+        let low = self.blocks.get(index).copied().unwrap_or(0) as u8 as u16;
+        let high = self.add.map_or(0, |add| nibble(add, index)) as u16;
+        (high << 8) | low
+    }
+
+    fn data_at(&self, index: usize) -> u8 {
+        self.data.map_or(0, |data| nibble(data, index))
+    }
+}
+
+/// Build a palette from numeric block IDs and data values, with the
+/// section below for blocks that depend on the one under them
+fn numeric_block_states(section: NumericBlocks, below: Option<NumericBlocks>) -> BlockStates {
+    const BLOCK_COUNT: usize = 4096;
+    const LAYER: usize = 256;
+    const DOUBLE_PLANT: u16 = 175;
+
+    let mut palette = Vec::new();
+    let mut palette_indices: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut unpacked = Vec::with_capacity(BLOCK_COUNT);
+
+    for index in 0..BLOCK_COUNT {
+        let id = section.id_at(index);
+        let mut value = section.data_at(index);
+
+        // The upper half of a tall plant only stores its half, the kind is
+        // on the lower half one block down, at the top of the section below
+        // for plants on a section's bottom layer
+        if id == DOUBLE_PLANT && value & 8 != 0 {
+            let lower = match index.checked_sub(LAYER) {
+                Some(lower) => Some((section, lower)),
+                None => below.map(|below| (below, index + BLOCK_COUNT - LAYER)),
+            };
+            if let Some((blocks, lower)) = lower
+                && blocks.id_at(lower) == id
+            {
+                value = blocks.data_at(lower) | 8;
+            }
+        }
+
+        let palette_index = *palette_indices.entry((id, value)).or_insert_with(|| {
+            palette.push(block_ids::flatten(id, value));
+            (palette.len() - 1) as u16
+        });
+        unpacked.push(palette_index);
+    }
+
+    BlockStates {
+        palette,
+        data: None,
+        unpacked_data: Some(unpacked),
+        palette_ids: Vec::new(),
+    }
+}
+
+/// The chunk's biome IDs, either per column or per 4x4x4 cell
+enum BiomeIds {
+    Columns(Vec<u32>),
+    Cells(Vec<u32>),
+}
+
+fn biome_id_array(biomes: &Value) -> Option<BiomeIds> {
+    let ids: Vec<u32> = match biomes {
+        Value::IntArray(ids) => ids.iter().map(|&id| id as u32).collect(),
+        Value::ByteArray(ids) => ids.iter().map(|&id| id as u8 as u32).collect(),
+        _ => return None,
+    };
+    match ids.len() {
+        256 => Some(BiomeIds::Columns(ids)),
+        1024 => Some(BiomeIds::Cells(ids)),
+        _ => None,
+    }
+}
+
+/// Biomes of the 4x4x4 cells of the section at `section_y`
+fn section_biomes(ids: &BiomeIds, section_y: i8) -> Biomes {
+    let mut palette = Vec::new();
+    let mut unpacked = Vec::with_capacity(64);
+
+    for cy in 0..4 {
+        for cz in 0..4 {
+            for cx in 0..4 {
+                let id = match ids {
+                    // Use the column at the corner of the cell
+                    BiomeIds::Columns(ids) => ids[cz * 64 + cx * 4],
+                    BiomeIds::Cells(ids) => {
+                        let y = (section_y as isize * 4 + cy).clamp(0, 63) as usize;
+                        ids[y * 16 + cz * 4 + cx]
+                    }
+                };
+                let name = biome_ids::biome_name(id);
+                let palette_index = match palette.iter().position(|entry| entry == name) {
+                    Some(position) => position,
+                    None => {
+                        palette.push(name.to_string());
+                        palette.len() - 1
+                    }
+                };
+                unpacked.push(palette_index as u16);
+            }
+        }
+    }
+
+    Biomes {
+        palette,
+        data: None,
+        unpacked_data: Some(unpacked),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fastnbt::nbt;
+
+    use crate::block_registry::BlockRegistry;
+    use crate::blocks::BlockMetadata;
+    use crate::coords::chunk_local_block_coord::ChunkLocalBlockCoord;
+
+    use super::*;
+
+    #[test]
+    fn numeric_chunks_are_flattened() {
+        let mut blocks = vec![0i8; 4096];
+        let mut data = vec![0i8; 2048];
+        // Granite at index 0, a spruce log along x at index 1
+        blocks[0] = 1;
+        data[0] = 1;
+        blocks[1] = 17;
+        data[0] |= 0b0101 << 4;
+        // A lilac, with its upper half one block up
+        blocks[512] = -81;
+        blocks[768] = -81;
+        data[256] = 1;
+        data[384] = 8;
+
+        let nbt = nbt!({
+            "DataVersion": 1343,
+            "Level": {
+                "xPos": 3,
+                "zPos": -2,
+                "Sections": [{
+                    "Y": 0i8,
+                    "Blocks": ByteArray::new(blocks),
+                    "Data": ByteArray::new(data),
+                }],
+                "Biomes": ByteArray::new(vec![6; 256]),
+            },
+        });
+        let mut chunk: Chunk = fastnbt::from_value(&nbt).unwrap();
+        assert_eq!((chunk.x_pos, chunk.z_pos), (3, -2));
+        let mut registry = BlockRegistry::new(BlockMetadata::bundled());
+        chunk.intern_blocks(&mut registry);
+
+        let section = &chunk.sections[0];
+        let at = |lx, ly, lz| {
+            let id = section.block_id_at(ChunkLocalBlockCoord { lx, ly, lz });
+            id.map(|id| registry.get(id).state.to_string())
+        };
+        assert_eq!(at(0, 0, 0).as_deref(), Some("minecraft:granite"));
+        assert_eq!(at(1, 0, 0).as_deref(), Some("minecraft:spruce_log[axis=x]"));
+        assert_eq!(at(0, 2, 0).as_deref(), Some("minecraft:lilac[half=lower]"));
+        assert_eq!(at(0, 3, 0).as_deref(), Some("minecraft:lilac[half=upper]"));
+        assert_eq!(at(5, 5, 5).as_deref(), Some("minecraft:air"));
+        assert_eq!(
+            section.biome_at(ChunkLocalBlockCoord {
+                lx: 9,
+                ly: 9,
+                lz: 9
+            }),
+            Some("minecraft:swamp")
+        );
+    }
+
+    #[test]
+    fn tall_plants_find_their_kind_across_sections() {
+        // A rose bush on the top layer of the first section, with its upper
+        // half on the bottom layer of the next
+        let mut lower_blocks = vec![0i8; 4096];
+        let mut lower_data = vec![0i8; 2048];
+        lower_blocks[15 * 256] = -81;
+        lower_data[15 * 128] = 4;
+        let mut upper_blocks = vec![0i8; 4096];
+        let mut upper_data = vec![0i8; 2048];
+        upper_blocks[0] = -81;
+        upper_data[0] = 8;
+
+        let nbt = nbt!({
+            "DataVersion": 1343,
+            "Level": {
+                "xPos": 0,
+                "zPos": 0,
+                "Sections": [
+                    {
+                        "Y": 1i8,
+                        "Blocks": ByteArray::new(upper_blocks),
+                        "Data": ByteArray::new(upper_data),
+                    },
+                    {
+                        "Y": 0i8,
+                        "Blocks": ByteArray::new(lower_blocks),
+                        "Data": ByteArray::new(lower_data),
+                    },
+                ],
+            },
+        });
+        let mut chunk: Chunk = fastnbt::from_value(&nbt).unwrap();
+        let mut registry = BlockRegistry::new(BlockMetadata::bundled());
+        chunk.intern_blocks(&mut registry);
+
+        let at = |section: &Section, ly| {
+            let id = section.block_id_at(ChunkLocalBlockCoord { lx: 0, ly, lz: 0 });
+            id.map(|id| registry.get(id).state.to_string())
+        };
+        assert_eq!(
+            at(&chunk.sections[1], 15).as_deref(),
+            Some("minecraft:rose_bush[half=lower]")
+        );
+        assert_eq!(
+            at(&chunk.sections[0], 0).as_deref(),
+            Some("minecraft:rose_bush[half=upper]")
+        );
+    }
+
+    #[test]
+    fn palette_chunks_keep_their_palette() {
+        let nbt = nbt!({
+            "DataVersion": 2730,
+            "Level": {
+                "xPos": 0,
+                "zPos": 0,
+                "Sections": [
+                    {"Y": -1i8},
+                    {
+                        "Y": 1i8,
+                        "Palette": [{"Name": "minecraft:stone"}],
+                    },
+                ],
+                "Biomes": fastnbt::IntArray::new(vec![1; 1024]),
+            },
+        });
+        let mut chunk: Chunk = fastnbt::from_value(&nbt).unwrap();
+        let mut registry = BlockRegistry::new(BlockMetadata::bundled());
+        chunk.intern_blocks(&mut registry);
+
+        assert!(chunk.sections[0].block_states.is_none());
+        let origin = ChunkLocalBlockCoord {
+            lx: 0,
+            ly: 0,
+            lz: 0,
+        };
+        let id = chunk.sections[1].block_id_at(origin).unwrap();
+        assert_eq!(registry.get(id).state.name, "minecraft:stone");
+        assert_eq!(chunk.sections[1].biome_at(origin), Some("minecraft:plains"));
+    }
+}
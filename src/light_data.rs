@@ -15,6 +15,11 @@ impl LightData {
         factor
     }
 
+    /// Factor of the brightest face, for blocks lit from every side
+    pub fn brightest_factor(&self) -> f64 {
+        Self::calc_factor(self.light_east.max(self.light_south).max(self.light_top))
+    }
+
     pub fn factor(&self, face: BlockFace) -> f64 {
         match face {
             BlockFace::East => Self::calc_factor(self.light_east),
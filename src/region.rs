@@ -1,73 +1,488 @@
 use std::{
-    fs::File,
-    io::{BufReader, Read, Seek, SeekFrom},
-    path::PathBuf,
+    borrow::Cow,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
 };
 
-use flate2::bufread::ZlibDecoder;
+use rayon::prelude::*;
 
-use crate::{chunk::Chunk, coords::world_chunk_coord::WorldChunkCoord};
+use crate::{
+    chunk::Chunk,
+    compression::CompressionScheme,
+    coords::{
+        region_coord::{REGION_SIZE, RegionCoord},
+        world_chunk_coord::WorldChunkCoord,
+    },
+    region_error::{ChunkError, RegionError},
+};
+
+/// Size of a region file sector in bytes
+pub const SECTOR_SIZE: usize = 4096;
+/// Length of the location and timestamp tables at the start of a region file
+const HEADER_SIZE: usize = 2 * SECTOR_SIZE;
+
+/// Entry of the location table: where a chunk's data lives in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkLocation {
+    /// Offset of the chunk data from the start of the file, in sectors
+    pub sector_offset: u32,
+    /// Number of sectors allocated to the chunk
+    pub sector_count: u8,
+}
+
+impl ChunkLocation {
+    fn from_be_bytes(bytes: [u8; 4]) -> Self {
+        let location = u32::from_be_bytes(bytes);
+        Self {
+            sector_offset: location >> 8,
+            sector_count: (location & 0xFF) as u8,
+        }
+    }
+
+    /// Whether the slot holds a chunk at all
+    pub fn is_present(&self) -> bool {
+        self.sector_offset != 0
+    }
+
+    pub fn byte_offset(&self) -> usize {
+        self.sector_offset as usize * SECTOR_SIZE
+    }
+
+    pub fn byte_len(&self) -> usize {
+        self.sector_count as usize * SECTOR_SIZE
+    }
+}
+
+/// Header preceding each chunk's data in the region file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkHeader {
+    /// Length of the payload plus the compression byte
+    pub length: u32,
+    pub compression: CompressionScheme,
+    /// The payload lives in a `c.X.Z.mcc` file next to the region
+    pub external: bool,
+}
+
+/// A region (`.mca`) file read into memory, with its header tables parsed
+pub struct RegionFile {
+    coord: RegionCoord,
+    /// Folder searched for external `.mcc` chunk files
+    dir: Option<PathBuf>,
+    data: Vec<u8>,
+    locations: Vec<ChunkLocation>,
+    timestamps: Vec<u32>,
+}
+
+impl RegionFile {
+    /// Open a region file, taking its coordinate from the file name
+    pub fn open(path: &Path) -> Result<Self, RegionError> {
+        let coord = coord_from_path(path)?;
+        let data = fs::read(path).map_err(|err| RegionError::Io(path.to_path_buf(), err))?;
+        let mut region = Self::from_bytes(coord, data)?;
+        region.dir = path.parent().map(Path::to_path_buf);
+        Ok(region)
+    }
+
+    /// Parse the header of an in-memory region file. External chunks cannot
+    /// be read from a region created this way.
+    pub fn from_bytes(coord: RegionCoord, data: Vec<u8>) -> Result<Self, RegionError> {
+        if data.len() < HEADER_SIZE {
+            return Err(RegionError::TruncatedHeader { len: data.len() });
+        }
+
+        let (location_table, rest) = data.split_at(SECTOR_SIZE);
+        let timestamp_table = &rest[..SECTOR_SIZE];
 
-pub fn read_chunk(path: PathBuf, chunk_coord: &WorldChunkCoord) -> Option<Chunk> {
-    let file = File::open(path).ok()?;
-    let mut reader = BufReader::new(file);
+        let locations = parse_location_table(location_table);
+        let timestamps = timestamp_table
+            .chunks_exact(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
 
-    // Read location table (first 4096 bytes, uncompressed)
-    let mut location_table = [0u8; 4096];
-    reader.read_exact(&mut location_table).ok()?;
+        Ok(Self {
+            coord,
+            dir: None,
+            data,
+            locations,
+            timestamps,
+        })
+    }
 
-    // Read timestamp table (next 4096 bytes, uncompressed)
-    let mut timestamp_table = [0u8; 4096];
-    reader.read_exact(&mut timestamp_table).ok()?;
+    pub fn coord(&self) -> RegionCoord {
+        self.coord
+    }
 
-    // Handle negative chunk coordinates properly
-    let local_x = chunk_coord.cx.rem_euclid(32);
-    let local_z = chunk_coord.cz.rem_euclid(32);
-    let chunk_index = (local_x + local_z * 32) * 4;
-    let chunk_index = chunk_index as usize;
+    /// Length of the file in bytes
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
 
-    // Parse location as big-endian u32
-    let location = u32::from_be_bytes([
-        location_table[chunk_index],
-        location_table[chunk_index + 1],
-        location_table[chunk_index + 2],
-        location_table[chunk_index + 3],
-    ]);
+    /// The 1024 location table entries, indexed by `WorldChunkCoord::region_index`
+    pub fn locations(&self) -> &[ChunkLocation] {
+        &self.locations
+    }
 
-    let offset = ((location >> 8) * 4096) as u64;
-    let _sectors = (location & 0xFF) as u8;
+    /// Last modification times (seconds since the epoch), indexed like `locations`
+    pub fn timestamps(&self) -> &[u32] {
+        &self.timestamps
+    }
 
-    if offset == 0 {
-        return None; // Chunk doesn't exist
+    pub fn location(&self, chunk_coord: &WorldChunkCoord) -> ChunkLocation {
+        self.locations[chunk_coord.region_index()]
     }
 
-    // Seek to chunk data
-    reader.seek(SeekFrom::Start(offset)).ok()?;
+    pub fn timestamp(&self, chunk_coord: &WorldChunkCoord) -> u32 {
+        self.timestamps[chunk_coord.region_index()]
+    }
+
+    /// World coordinates of every chunk with a non-empty location entry
+    pub fn present_chunks(&self) -> impl Iterator<Item = WorldChunkCoord> + '_ {
+        present_chunks(self.coord, &self.locations)
+    }
+
+    /// Read the length and compression of a present chunk
+    pub fn chunk_header(&self, chunk_coord: &WorldChunkCoord) -> Result<ChunkHeader, ChunkError> {
+        let location = self.location(chunk_coord);
+        if !location.is_present() {
+            return Err(ChunkError::Absent);
+        }
+
+        // Chunk header: 4 byte big-endian length, then the compression byte
+        let offset = location.byte_offset();
+        let header = self
+            .data
+            .get(offset..offset + 5)
+            .filter(|_| offset >= HEADER_SIZE)
+            .ok_or(ChunkError::SectorOutOfBounds {
+                offset,
+                file_len: self.data.len(),
+            })?;
+        let (compression, external) = CompressionScheme::from_byte(header[4]);
+        Ok(ChunkHeader {
+            length: u32::from_be_bytes([header[0], header[1], header[2], header[3]]),
+            compression,
+            external,
+        })
+    }
 
-    // Read chunk header (5 bytes)
-    let mut header = [0u8; 5];
-    reader.read_exact(&mut header).ok()?;
+    /// The still-compressed payload of a chunk, from the region or its `.mcc` file
+    fn chunk_payload(
+        &self,
+        chunk_coord: &WorldChunkCoord,
+        header: &ChunkHeader,
+    ) -> Result<Cow<'_, [u8]>, ChunkError> {
+        if header.external {
+            let name = chunk_coord.external_file_name();
+            let path = match &self.dir {
+                Some(dir) => dir.join(name),
+                None => PathBuf::from(name),
+            };
+            return fs::read(&path)
+                .map(Cow::Owned)
+                .map_err(|err| ChunkError::MissingExternal(path, err));
+        }
+
+        // The length counts the compression byte and the payload, which
+        // after the 4 length bytes must fit in both the allocated sectors
+        // and the file
+        let location = self.location(chunk_coord);
+        let start = location.byte_offset() + 4;
+        let available = (location.byte_offset() + location.byte_len())
+            .min(self.data.len())
+            .saturating_sub(start);
+        let declared = header.length as usize;
+        if declared == 0 || declared > available {
+            return Err(ChunkError::LengthMismatch {
+                declared,
+                available,
+            });
+        }
+
+        Ok(Cow::Borrowed(&self.data[start + 1..start + declared]))
+    }
+
+    /// Decode a single chunk
+    pub fn read_chunk(&self, chunk_coord: &WorldChunkCoord) -> Result<Chunk, ChunkError> {
+        let header = self.chunk_header(chunk_coord)?;
+        if !header.compression.is_supported() {
+            return Err(ChunkError::UnsupportedCompression(header.compression));
+        }
+        let payload = self.chunk_payload(chunk_coord, &header)?;
+
+        let decompressed = match header.compression {
+            CompressionScheme::Uncompressed => payload,
+            compression => Cow::Owned(
+                compression
+                    .decompress(&payload)
+                    .map_err(|err| ChunkError::Decompression(compression, err))?,
+            ),
+        };
+
+        // Parse as NBT (owned deserialization)
+        fastnbt::from_bytes(&decompressed).map_err(ChunkError::Nbt)
+    }
+
+    /// Decode and unpack the given chunks in parallel
+    pub fn read_chunks(
+        &self,
+        chunk_coords: &[WorldChunkCoord],
+    ) -> Vec<(WorldChunkCoord, Result<Chunk, ChunkError>)> {
+        chunk_coords
+            .par_iter()
+            .map(|coord| {
+                let chunk = self.read_chunk(coord).and_then(|mut chunk| {
+                    chunk.ensure_unpacked()?;
+                    Ok(chunk)
+                });
+                (*coord, chunk)
+            })
+            .collect()
+    }
+}
 
-    let data_length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
-    let compression_type = header[4];
+/// Chunks present in a region file, read from its location table alone
+pub fn read_present_chunks(path: &Path) -> Result<Vec<WorldChunkCoord>, RegionError> {
+    let coord = coord_from_path(path)?;
+    let mut location_table = vec![0u8; SECTOR_SIZE];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut location_table))
+        .map_err(|err| RegionError::Io(path.to_path_buf(), err))?;
 
-    if compression_type != 2 {
-        eprintln!(
-            "Warning: unexpected compression type {} (expected 2 for zlib)",
-            compression_type
+    let locations = parse_location_table(&location_table);
+    Ok(present_chunks(coord, &locations).collect())
+}
+
+fn coord_from_path(path: &Path) -> Result<RegionCoord, RegionError> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(RegionCoord::from_file_name)
+        .ok_or_else(|| RegionError::InvalidFileName(path.to_path_buf()))
+}
+
+fn parse_location_table(table: &[u8]) -> Vec<ChunkLocation> {
+    table
+        .chunks_exact(4)
+        .map(|b| ChunkLocation::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn present_chunks(
+    coord: RegionCoord,
+    locations: &[ChunkLocation],
+) -> impl Iterator<Item = WorldChunkCoord> + '_ {
+    locations
+        .iter()
+        .enumerate()
+        .filter(|(_, location)| location.is_present())
+        .map(move |(index, _)| {
+            let size = REGION_SIZE as usize;
+            coord.chunk_coord(index % size, index / size)
+        })
+}
+
+/// Region files built in memory, for the tests of anything reading them
+#[cfg(test)]
+pub(crate) mod test_util {
+    use std::io::Write;
+
+    use flate2::{
+        Compression,
+        write::{GzEncoder, ZlibEncoder},
+    };
+
+    use super::*;
+
+    pub(crate) fn empty_chunk_nbt(coord: &WorldChunkCoord) -> Vec<u8> {
+        fastnbt::to_bytes(&fastnbt::nbt!({
+            "DataVersion": 3953,
+            "xPos": coord.cx as i32,
+            "zPos": coord.cz as i32,
+            "sections": [],
+        }))
+        .unwrap()
+    }
+
+    pub(crate) fn compress(compression_byte: u8, nbt: &[u8]) -> Vec<u8> {
+        match compression_byte {
+            1 => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(nbt).unwrap();
+                encoder.finish().unwrap()
+            }
+            2 => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(nbt).unwrap();
+                encoder.finish().unwrap()
+            }
+            _ => nbt.to_vec(),
+        }
+    }
+
+    /// Build a region file holding an empty chunk per `(coord, timestamp)` entry
+    pub(crate) fn build_region(chunks: &[(WorldChunkCoord, u32)]) -> Vec<u8> {
+        let entries: Vec<_> = chunks.iter().map(|&(c, t)| (c, t, 2)).collect();
+        build_region_with(&entries)
+    }
+
+    /// Like `build_region`, with an explicit compression byte per chunk
+    pub(crate) fn build_region_with(chunks: &[(WorldChunkCoord, u32, u8)]) -> Vec<u8> {
+        let mut data = vec![0u8; HEADER_SIZE];
+
+        for (coord, timestamp, compression_byte) in chunks {
+            let compressed = compress(*compression_byte, &empty_chunk_nbt(coord));
+
+            let sector_offset = (data.len() / SECTOR_SIZE) as u32;
+            data.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+            data.push(*compression_byte);
+            data.extend_from_slice(&compressed);
+            data.resize(data.len().div_ceil(SECTOR_SIZE) * SECTOR_SIZE, 0);
+            let sector_count = data.len() as u32 / SECTOR_SIZE as u32 - sector_offset;
+
+            let index = coord.region_index() * 4;
+            data[index..index + 4]
+                .copy_from_slice(&((sector_offset << 8) | sector_count).to_be_bytes());
+            data[SECTOR_SIZE + index..SECTOR_SIZE + index + 4]
+                .copy_from_slice(&timestamp.to_be_bytes());
+        }
+
+        data
+    }
+
+    /// Location table entry of a chunk in a built region
+    pub(crate) fn region_location(data: &[u8], coord: &WorldChunkCoord) -> ChunkLocation {
+        let index = coord.region_index() * 4;
+        ChunkLocation::from_be_bytes(data[index..index + 4].try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn region_file_reads_header_and_chunks() {
+        let coord = RegionCoord { rx: -1, rz: 2 };
+        let first = WorldChunkCoord { cx: -32, cz: 64 };
+        let second = WorldChunkCoord { cx: -3, cz: 70 };
+        let region =
+            RegionFile::from_bytes(coord, build_region(&[(first, 100), (second, 200)])).unwrap();
+
+        assert_eq!(
+            region.present_chunks().collect::<Vec<_>>(),
+            vec![first, second]
         );
-        return None;
+        assert_eq!(region.timestamp(&second), 200);
+        assert_eq!(region.location(&first).sector_offset, 2);
+        assert!(region.read_chunk(&second).is_ok());
+        assert!(matches!(
+            region.read_chunk(&WorldChunkCoord { cx: -1, cz: 64 }),
+            Err(ChunkError::Absent)
+        ));
+        let chunks = region.read_chunks(&[first, second]);
+        assert!(chunks.iter().all(|(_, chunk)| chunk.is_ok()));
+    }
+
+    #[test]
+    fn region_file_decodes_each_compression_scheme() {
+        let coord = RegionCoord { rx: 0, rz: 0 };
+        let gzip = WorldChunkCoord { cx: 0, cz: 0 };
+        let raw = WorldChunkCoord { cx: 1, cz: 0 };
+        let external = WorldChunkCoord { cx: 2, cz: 0 };
+        let region = RegionFile::from_bytes(
+            coord,
+            build_region_with(&[(gzip, 0, 1), (raw, 0, 3), (external, 0, 0x82)]),
+        )
+        .unwrap();
+
+        let header = region.chunk_header(&gzip).unwrap();
+        assert_eq!(header.compression, CompressionScheme::GZip);
+        assert!(region.read_chunk(&gzip).is_ok());
+        assert!(region.read_chunk(&raw).is_ok());
+
+        // No directory to find the .mcc file in
+        let header = region.chunk_header(&external).unwrap();
+        assert_eq!(header.compression, CompressionScheme::Zlib);
+        assert!(header.external);
+        assert!(matches!(
+            region.read_chunk(&external),
+            Err(ChunkError::MissingExternal(..))
+        ));
+    }
+
+    #[test]
+    fn region_file_reports_corrupt_chunks() {
+        let coord = RegionCoord { rx: 0, rz: 0 };
+        let past_end = WorldChunkCoord { cx: 0, cz: 0 };
+        let too_long = WorldChunkCoord { cx: 1, cz: 0 };
+        let garbage = WorldChunkCoord { cx: 2, cz: 0 };
+        let mut data = build_region(&[(too_long, 0), (garbage, 0)]);
+
+        // Point a chunk far past the end of the file
+        data[..4].copy_from_slice(&((100 << 8) | 1u32).to_be_bytes());
+        // Claim more bytes than the single allocated sector holds
+        let offset = region_location(&data, &too_long).byte_offset();
+        data[offset..offset + 4].copy_from_slice(&(SECTOR_SIZE as u32 * 2).to_be_bytes());
+        // Corrupt the zlib stream
+        let offset = region_location(&data, &garbage).byte_offset();
+        data[offset + 5..offset + 9].copy_from_slice(&[0xFF; 4]);
+
+        let region = RegionFile::from_bytes(coord, data).unwrap();
+        assert!(matches!(
+            region.read_chunk(&past_end),
+            Err(ChunkError::SectorOutOfBounds { .. })
+        ));
+        assert!(matches!(
+            region.read_chunk(&too_long),
+            Err(ChunkError::LengthMismatch {
+                declared: 8192,
+                available: 4092,
+            })
+        ));
+        assert!(matches!(
+            region.read_chunk(&garbage),
+            Err(ChunkError::Decompression(CompressionScheme::Zlib, _))
+        ));
     }
 
-    // Read compressed chunk data (data_length - 1 because we already read compression byte)
-    let mut compressed_data = vec![0u8; (data_length - 1) as usize];
-    reader.read_exact(&mut compressed_data).ok()?;
+    #[test]
+    fn region_file_reads_external_chunks_next_to_it() {
+        let dir = std::env::temp_dir().join(format!("region-external-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let external = WorldChunkCoord { cx: 2, cz: 0 };
+        // The copy in the region is not zlib, so only the .mcc file decodes
+        fs::write(
+            dir.join("r.0.0.mca"),
+            build_region_with(&[(external, 0, 0x82)]),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(external.external_file_name()),
+            compress(2, &empty_chunk_nbt(&external)),
+        )
+        .unwrap();
 
-    // Decompress with zlib
-    let mut decoder = ZlibDecoder::new(&compressed_data[..]);
-    let mut decompressed = Vec::new();
-    decoder.read_to_end(&mut decompressed).ok()?;
+        let region = RegionFile::open(&dir.join("r.0.0.mca")).unwrap();
+        let chunk = region.read_chunk(&external);
+        fs::remove_dir_all(&dir).unwrap();
+        let chunk = chunk.unwrap();
+        assert_eq!((chunk.x_pos, chunk.z_pos), (2, 0));
+    }
+
+    #[test]
+    fn region_file_rejects_truncated_header() {
+        let coord = RegionCoord { rx: 0, rz: 0 };
+        assert!(RegionFile::from_bytes(coord, vec![0; 100]).is_err());
+    }
 
-    // Parse as NBT (owned deserialization)
-    fastnbt::from_bytes(&decompressed).ok()
+    #[test]
+    fn region_coord_parses_file_names() {
+        assert_eq!(
+            RegionCoord::from_file_name("r.-2.13.mca"),
+            Some(RegionCoord { rx: -2, rz: 13 })
+        );
+        assert_eq!(RegionCoord::from_file_name("r.1.mca"), None);
+        assert_eq!(RegionCoord::from_file_name("c.1.2.mcc"), None);
+    }
 }
@@ -0,0 +1,113 @@
+use core::fmt;
+use std::{io, path::PathBuf};
+
+use crate::{compression::CompressionScheme, section::PackingError};
+
+/// Failure to open a region file as a whole
+#[derive(Debug)]
+pub enum RegionError {
+    Io(PathBuf, io::Error),
+    /// The file name does not follow the `r.X.Z.mca` pattern
+    InvalidFileName(PathBuf),
+    /// The file is too short to hold the location and timestamp tables
+    TruncatedHeader {
+        len: usize,
+    },
+}
+
+impl fmt::Display for RegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegionError::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            RegionError::InvalidFileName(path) => {
+                write!(f, "{} is not named like a region file", path.display())
+            }
+            RegionError::TruncatedHeader { len } => {
+                write!(f, "file is {} bytes, shorter than the region header", len)
+            }
+        }
+    }
+}
+
+/// Failure to read a single chunk out of a region
+#[derive(Debug)]
+pub enum ChunkError {
+    /// The location table has no entry for the chunk
+    Absent,
+    /// The location table points outside the file or into its header
+    SectorOutOfBounds {
+        offset: usize,
+        file_len: usize,
+    },
+    /// The length in the chunk header does not fit the space available to it.
+    /// Both count the compression byte and the payload after it
+    LengthMismatch {
+        declared: usize,
+        available: usize,
+    },
+    /// The `.mcc` file of an oversized chunk could not be read
+    MissingExternal(PathBuf, io::Error),
+    UnsupportedCompression(CompressionScheme),
+    Decompression(CompressionScheme, io::Error),
+    /// The decompressed data does not match the expected chunk NBT schema
+    Nbt(fastnbt::error::Error),
+    /// A section's packed block states or biomes do not fit its palette
+    InvalidSection {
+        section_y: i8,
+        error: PackingError,
+    },
+    /// A heightmap does not hold one height per column
+    InvalidHeightmap(PackingError),
+}
+
+impl ChunkError {
+    /// Short label used to group failures in summaries
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ChunkError::Absent => "absent",
+            ChunkError::SectorOutOfBounds { .. } => "sector out of bounds",
+            ChunkError::LengthMismatch { .. } => "length mismatch",
+            ChunkError::MissingExternal(..) => "missing external file",
+            ChunkError::UnsupportedCompression(_) => "unsupported compression",
+            ChunkError::Decompression(..) => "decompression failed",
+            ChunkError::Nbt(_) => "invalid NBT",
+            ChunkError::InvalidSection { .. } => "invalid section data",
+            ChunkError::InvalidHeightmap(_) => "invalid heightmap",
+        }
+    }
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkError::Absent => write!(f, "chunk is not present in the region"),
+            ChunkError::SectorOutOfBounds { offset, file_len } => write!(
+                f,
+                "chunk data at byte {} is outside the {} byte region file",
+                offset, file_len
+            ),
+            ChunkError::LengthMismatch {
+                declared,
+                available,
+            } => write!(
+                f,
+                "chunk declares {} bytes but only {} are available",
+                declared, available
+            ),
+            ChunkError::MissingExternal(path, err) => {
+                write!(f, "failed to read {}: {}", path.display(), err)
+            }
+            ChunkError::UnsupportedCompression(compression) => {
+                write!(f, "unsupported compression scheme {}", compression)
+            }
+            ChunkError::Decompression(compression, err) => {
+                write!(f, "{} decompression failed: {}", compression, err)
+            }
+            ChunkError::Nbt(err) => write!(f, "invalid chunk NBT: {}", err),
+            ChunkError::InvalidSection { section_y, error } => {
+                write!(f, "section y={} is invalid: {}", section_y, error)
+            }
+            ChunkError::InvalidHeightmap(error) => write!(f, "heightmap is invalid: {}", error),
+        }
+    }
+}
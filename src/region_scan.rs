@@ -0,0 +1,240 @@
+use core::fmt;
+
+use rayon::prelude::*;
+
+use crate::{
+    coords::world_chunk_coord::WorldChunkCoord,
+    region::{RegionFile, SECTOR_SIZE},
+    region_error::ChunkError,
+};
+
+/// First sector available to chunk data, after the location and timestamp tables
+const FIRST_DATA_SECTOR: usize = 2;
+
+/// A structural or content problem found in a region file
+#[derive(Debug)]
+pub enum ScanIssue {
+    /// The chunk's sectors overlap the region header
+    OverlapsHeader { chunk: WorldChunkCoord },
+    /// Two chunks claim some of the same sectors
+    Overlap {
+        chunk: WorldChunkCoord,
+        other: WorldChunkCoord,
+    },
+    /// The chunk's sectors extend past the end of the file
+    PastEndOfFile {
+        chunk: WorldChunkCoord,
+        end_sector: usize,
+        file_sectors: usize,
+    },
+    /// The location entry or chunk header has a length of zero
+    ZeroLength { chunk: WorldChunkCoord },
+    /// The chunk header declares more bytes than its sectors hold
+    LengthExceedsSectors {
+        chunk: WorldChunkCoord,
+        declared: usize,
+        allocated: usize,
+    },
+    /// The chunk data could not be decompressed, parsed or unpacked
+    Undecodable {
+        chunk: WorldChunkCoord,
+        error: ChunkError,
+    },
+    /// The coordinates stored in the chunk do not match its slot
+    PositionMismatch {
+        chunk: WorldChunkCoord,
+        stored: WorldChunkCoord,
+    },
+}
+
+impl fmt::Display for ScanIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanIssue::OverlapsHeader { chunk } => {
+                write!(f, "chunk ({}) overlaps the region header", chunk)
+            }
+            ScanIssue::Overlap { chunk, other } => {
+                write!(f, "chunk ({}) overlaps sectors of chunk ({})", chunk, other)
+            }
+            ScanIssue::PastEndOfFile {
+                chunk,
+                end_sector,
+                file_sectors,
+            } => write!(
+                f,
+                "chunk ({}) ends at sector {} but the file has {} sectors",
+                chunk, end_sector, file_sectors
+            ),
+            ScanIssue::ZeroLength { chunk } => write!(f, "chunk ({}) has zero length", chunk),
+            ScanIssue::LengthExceedsSectors {
+                chunk,
+                declared,
+                allocated,
+            } => write!(
+                f,
+                "chunk ({}) declares {} bytes in {} allocated bytes",
+                chunk, declared, allocated
+            ),
+            ScanIssue::Undecodable { chunk, error } => {
+                write!(f, "chunk ({}) is undecodable: {}", chunk, error)
+            }
+            ScanIssue::PositionMismatch { chunk, stored } => {
+                write!(f, "chunk ({}) stores position ({})", chunk, stored)
+            }
+        }
+    }
+}
+
+/// Check every present chunk of a region for corruption
+///
+/// Sector allocation is checked first; only chunks whose allocation is sound
+/// are decoded to validate their contents.
+pub fn scan_region(region: &RegionFile) -> Vec<ScanIssue> {
+    let file_sectors = region.len().div_ceil(SECTOR_SIZE);
+    let mut issues = Vec::new();
+    let mut sound = Vec::new();
+
+    // (first sector, end sector, chunk), sorted to sweep for overlaps
+    let mut allocations: Vec<(usize, usize, WorldChunkCoord)> = Vec::new();
+
+    for chunk in region.present_chunks() {
+        let location = region.location(&chunk);
+        let start = location.sector_offset as usize;
+        let end = start + location.sector_count as usize;
+        let issue_count = issues.len();
+
+        if location.sector_count == 0 {
+            issues.push(ScanIssue::ZeroLength { chunk });
+        } else {
+            allocations.push((start, end, chunk));
+        }
+        if start < FIRST_DATA_SECTOR {
+            issues.push(ScanIssue::OverlapsHeader { chunk });
+        }
+        if end > file_sectors {
+            issues.push(ScanIssue::PastEndOfFile {
+                chunk,
+                end_sector: end,
+                file_sectors,
+            });
+        }
+
+        if issues.len() == issue_count {
+            match region.chunk_header(&chunk) {
+                Ok(header) if header.length == 0 => issues.push(ScanIssue::ZeroLength { chunk }),
+                Ok(header)
+                    if !header.external && header.length as usize + 4 > location.byte_len() =>
+                {
+                    issues.push(ScanIssue::LengthExceedsSectors {
+                        chunk,
+                        declared: header.length as usize,
+                        allocated: location.byte_len(),
+                    })
+                }
+                Ok(_) => sound.push(chunk),
+                Err(error) => issues.push(ScanIssue::Undecodable { chunk, error }),
+            }
+        }
+    }
+
+    allocations.sort_by_key(|&(start, end, _)| (start, end));
+    let mut furthest: Option<(usize, WorldChunkCoord)> = None;
+    for &(start, end, chunk) in &allocations {
+        if let Some((furthest_end, other)) = furthest {
+            if start < furthest_end {
+                issues.push(ScanIssue::Overlap { chunk, other });
+            }
+            if end <= furthest_end {
+                continue;
+            }
+        }
+        furthest = Some((end, chunk));
+    }
+
+    let content_issues: Vec<ScanIssue> = sound
+        .par_iter()
+        .filter_map(|&chunk| {
+            let data = region.read_chunk(&chunk).and_then(|mut data| {
+                data.ensure_unpacked()?;
+                Ok(data)
+            });
+            match data {
+                Err(error) => Some(ScanIssue::Undecodable { chunk, error }),
+                Ok(data) => {
+                    let stored = WorldChunkCoord {
+                        cx: data.x_pos as isize,
+                        cz: data.z_pos as isize,
+                    };
+                    (stored != chunk).then_some(ScanIssue::PositionMismatch { chunk, stored })
+                }
+            }
+        })
+        .collect();
+    issues.extend(content_issues);
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::region_coord::RegionCoord;
+    use crate::region::test_util::{build_region, region_location};
+
+    fn set_location(data: &mut [u8], chunk: &WorldChunkCoord, offset: u32, count: u32) {
+        let index = chunk.region_index() * 4;
+        data[index..index + 4].copy_from_slice(&((offset << 8) | count).to_be_bytes());
+    }
+
+    #[test]
+    fn scan_finds_allocation_and_content_problems() {
+        let coord = RegionCoord { rx: 0, rz: 0 };
+        let a = WorldChunkCoord { cx: 0, cz: 0 };
+        let b = WorldChunkCoord { cx: 1, cz: 0 };
+        let c = WorldChunkCoord { cx: 2, cz: 0 };
+        let d = WorldChunkCoord { cx: 3, cz: 0 };
+        let moved = WorldChunkCoord { cx: 4, cz: 0 };
+        let mut data = build_region(&[(a, 0), (b, 0), (c, 0)]);
+
+        // `b` shares `a`'s sector, `c` runs past the end and `d` has no sectors
+        let a_offset = region_location(&data, &a).sector_offset;
+        set_location(&mut data, &b, a_offset, 1);
+        set_location(&mut data, &c, 4, 3);
+        set_location(&mut data, &d, 9, 0);
+        // `moved` points at the data of `a`, which stores position 0,0
+        set_location(&mut data, &moved, a_offset, 1);
+
+        let region = RegionFile::from_bytes(coord, data).unwrap();
+        let issues = scan_region(&region);
+
+        assert!(
+            issues
+                .iter()
+                .any(|i| matches!(i, ScanIssue::Overlap { .. }))
+        );
+        assert!(
+            issues
+                .iter()
+                .any(|i| matches!(i, ScanIssue::PastEndOfFile { chunk, .. } if *chunk == c))
+        );
+        assert!(
+            issues
+                .iter()
+                .any(|i| matches!(i, ScanIssue::ZeroLength { chunk } if *chunk == d))
+        );
+        assert!(issues.iter().any(
+            |i| matches!(i, ScanIssue::PositionMismatch { chunk, stored } if *chunk == moved && *stored == a)
+        ));
+    }
+
+    #[test]
+    fn scan_accepts_clean_region() {
+        let coord = RegionCoord { rx: 0, rz: 0 };
+        let chunks = [
+            (WorldChunkCoord { cx: 0, cz: 0 }, 0),
+            (WorldChunkCoord { cx: 5, cz: 7 }, 0),
+        ];
+        let region = RegionFile::from_bytes(coord, build_region(&chunks)).unwrap();
+        assert!(scan_region(&region).is_empty());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod mode;
-mod render_cube;
+pub mod render_cube;
+pub mod render_fluid;
+mod render_shape;
 pub mod renderer;
 mod transforms;
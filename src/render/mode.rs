@@ -1,3 +1,7 @@
 pub struct RenderMode {
     pub night: bool,
+    /// Darken chunks whose generation did not finish
+    pub dim_partial_chunks: bool,
+    /// Shade water by the depth to the ground below it
+    pub water_depth: bool,
 }
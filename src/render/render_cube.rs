@@ -1,157 +1,233 @@
-use crate::asset_cache::{AssetCache, BlockPartKey, BlockSpriteKey};
-use crate::coords::block_face::BlockFace;
+use crate::asset_cache::{AssetCache, BlockPartKey, LayerKey};
+use crate::coords::block_face::{BlockFace, VisibleFaces};
 use crate::light_data::LightData;
-use crate::render::renderer::SPRITE_SIZE;
 use crate::render::transforms::{BlockSpriteSide, transform_side, transform_top};
-use crate::utils::darken_image;
-use image::imageops::{crop_imm, overlay};
+use crate::utils::{darken_image, tint_image};
+use image::imageops::{
+    crop_imm, flip_horizontal, flip_vertical, overlay, rotate90, rotate180, rotate270,
+};
 use image::{Rgba, RgbaImage};
 
-fn name_top_side(name: String, face: &BlockFace) -> String {
-    match face {
-        BlockFace::Top => format!("{}_top", name),
-        BlockFace::South | BlockFace::East => format!("{}_side", name),
+/// Load a layer of a face of a block, transformed to its place in the sprite
+fn load_face(cache: &AssetCache, face: BlockFace, layer: &FaceLayer) -> RgbaImage {
+    let key = BlockPartKey {
+        face,
+        texture: layer.texture.clone(),
+        uv: layer.uv,
+        turns: layer.turns,
+    };
+    {
+        let block_part_cache = cache.block_part_cache.read().unwrap();
+        if let Some(cached) = block_part_cache.get(&key) {
+            return cached.clone();
+        }
     }
-}
 
-fn load_face(cache: &AssetCache, face: BlockFace, mut name: String) -> RgbaImage {
-    if name.starts_with("waxed_") {
-        name = name.replace("waxed_", "");
-    }
+    let mut block_part_cache = cache.block_part_cache.write().unwrap();
 
-    let search_name = match name.as_str() {
-        "lava" => "lava_still".to_string(),
-        "water" => "water_still".to_string(),
-        "grass_block" => match face {
-            BlockFace::Top => "grass_block_top".to_string(),
-            BlockFace::South | BlockFace::East => "grass_block_side".to_string(),
-        },
-        "dirt_path" => match face {
-            BlockFace::Top => "dirt_path_top".to_string(),
-            BlockFace::South | BlockFace::East => "dirt_path_side".to_string(),
-        },
-        "snow_block" => "snow".to_string(),
-        "stripped_oak_wood" => match face {
-            BlockFace::Top => "stripped_oak_log_top".to_string(),
-            BlockFace::South | BlockFace::East => "stripped_oak_log".to_string(),
-        },
-        "vault" => match face {
-            BlockFace::Top => "vault_top".to_string(),
-            BlockFace::South | BlockFace::East => "vault_front_off".to_string(),
-        },
-        "lilac" => "lilac_top".to_string(), // TODO block states
-        "rose_bush" => "rose_bush_bottom".to_string(), // TODO flowers
-        "peony" => "peony_top".to_string(),
-        "tall_seagrass" => "tall_seagrass_bottom".to_string(),
-        "glass_pane" => "glass".to_string(), // TODO
-        "hopper" => match face {
-            BlockFace::Top => "hopper_top".to_string(),
-            BlockFace::South | BlockFace::East => "hopper_outside".to_string(),
-        },
-        "bell" | "cauldron" | "stonecutter" | "composter" | "loom" | "hay_block" | "pumpkin"
-        | "bee_nest" | "sculk_catalyst" | "sculk_sensor" | "sculk_shrieker" | "barrel"
-        | "bone_block" => name_top_side(name, &face),
-        "oak_door" => "oak_door_top".to_string(), // TODO
-        "cobblestone_stairs" | "cobblestone_wall" => "cobblestone".to_string(), // TODO stairs
-        "oak_stairs" | "oak_slab" | "oak_fence_gate" | "oak_pressure_plate" | "oak_button"
-        | "oak_fence" => "oak_planks".to_string(), // TODO stairs
-        "stone_brick_stairs" | "stone_brick_slab" => "stone_bricks".to_string(), // TODO stairs
-        "mossy_stone_brick_stairs" | "mossy_stone_brick_slab" => "mossy_stone_bricks".to_string(), // TODO stairs
-        "oxidized_cut_copper_slab" | "oxidized_cut_copper_stairs" => {
-            "oxidized_cut_copper".to_string()
-        }
-        "oxidized_copper_door" => "oxidized_copper_door_top".to_string(),
-        "wheat" => "wheat_stage7".to_string(), // TODO block states
-        "carrots" => "carrots_stage3".to_string(), // TODO block states
-        "beetroots" => "beetroots_stage3".to_string(), // TODO block states
-        "potatoes" => "potatoes_stage3".to_string(),
-        "dispenser" => "dispenser_front".to_string(),
-        _ => name,
+    let texture_img = layer_texture(cache, layer);
+    let size = cache.sprite_size;
+    let img = match key.face {
+        BlockFace::East => transform_side(&texture_img, BlockSpriteSide::SideRight, size),
+        BlockFace::South => transform_side(&texture_img, BlockSpriteSide::SideLeft, size),
+        BlockFace::Top => transform_top(&texture_img, size),
     };
 
-    let key = BlockPartKey {
-        face: face.clone(),        // TODO
-        name: search_name.clone(), // TODO
+    block_part_cache.insert(key, img.clone());
+
+    img
+}
+
+/// The region of a layer's texture, turned the way it is drawn. Cached,
+/// as boxes of shaped blocks draw layers without a `BlockPartKey` of their own
+pub fn layer_texture(cache: &AssetCache, layer: &FaceLayer) -> RgbaImage {
+    let key = LayerKey {
+        texture: layer.texture.clone(),
+        uv: layer.uv,
+        turns: layer.turns,
     };
     {
-        let block_part_cache = cache.block_part_cache.read().unwrap();
-        if let Some(cached) = block_part_cache.get(&key) {
+        let layer_cache = cache.layer_cache.read().unwrap();
+        if let Some(cached) = layer_cache.get(&key) {
             return cached.clone();
         }
     }
 
-    let mut block_part_cache = cache.block_part_cache.write().unwrap();
+    let mut layer_cache = cache.layer_cache.write().unwrap();
 
     let mut texture_img = cache
-        .load_texture(search_name.as_str())
+        .load_texture(&layer.texture)
         .unwrap_or(create_missing_block_texture());
 
-    if texture_img.width() > 16 || texture_img.height() > 16 {
-        texture_img = crop_imm(&texture_img, 0, 0, 16, 16).to_image();
+    // Part of the texture, mirrored where the region runs backwards. The
+    // region is in 16ths of the texture, whatever its resolution
+    if layer.uv != FULL_UV {
+        let (texture_width, texture_height) = texture_img.dimensions();
+        let [u1, v1, u2, v2] = layer.uv.map(u32::from);
+        let [u1, u2] = [u1, u2].map(|u| u * texture_width / 16);
+        let [v1, v2] = [v1, v2].map(|v| v * texture_height / 16);
+        let width = u1.abs_diff(u2).max(1);
+        let height = v1.abs_diff(v2).max(1);
+        texture_img = crop_imm(&texture_img, u1.min(u2), v1.min(v2), width, height).to_image();
+        if u1 > u2 {
+            texture_img = flip_horizontal(&texture_img);
+        }
+        if v1 > v2 {
+            texture_img = flip_vertical(&texture_img);
+        }
     }
 
-    let img = match face {
-        BlockFace::East => transform_side(&texture_img, BlockSpriteSide::SideRight),
-        BlockFace::South => transform_side(&texture_img, BlockSpriteSide::SideLeft),
-        BlockFace::Top => transform_top(&texture_img),
+    let img = match layer.turns % 4 {
+        1 => rotate90(&texture_img),
+        2 => rotate180(&texture_img),
+        3 => rotate270(&texture_img),
+        _ => texture_img,
     };
 
-    block_part_cache.insert(key, img.clone());
+    layer_cache.insert(key, img.clone());
 
     img
 }
 
-/// Build a full isometric block from top and side textures
-/// Returns a 24x24 image
+/// Stack the layers of a face, applying the biome colour to the tinted
+/// ones. `None` for faces without layers
+fn build_face(
+    cache: &AssetCache,
+    face: BlockFace,
+    layers: &[FaceLayer],
+    tint: Option<[u8; 3]>,
+) -> Option<RgbaImage> {
+    let mut face_img: Option<RgbaImage> = None;
+    for layer in layers {
+        let mut part = load_face(cache, face, layer);
+        if let Some(tint) = tint
+            && layer.tinted
+        {
+            part = tint_image(&part, tint.map(|channel| channel as f64 / 255.0));
+        }
+        match &mut face_img {
+            Some(face_img) => overlay(face_img, &part, 0, 0),
+            None => face_img = Some(part),
+        }
+    }
+    face_img
+}
+
+/// Build an isometric block from the visible top and side textures
+/// Returns a square image of the sprite size
 pub fn render_block_3d(
     cache: &AssetCache,
-    name: &str,
-    plan: CubeSpritePlan,
-    light_data: LightData,
+    plan: &CubeSpritePlan,
+    faces: VisibleFaces,
+    light_data: &LightData,
+    tint: Option<[u8; 3]>,
 ) -> RgbaImage {
-    // cache read
-    let block_sprite_key = BlockSpriteKey {
-        light: light_data.clone(),
-        name: name.to_string(),
+    let size = cache.sprite_size;
+    let mut img = RgbaImage::new(size, size);
+
+    // Each face is darkened by its own light
+    let lit_face = |face: BlockFace, layers: &[FaceLayer]| {
+        build_face(cache, face, layers, tint)
+            .map(|face_img| darken_image(&face_img, light_data.factor(face)))
     };
+
+    // Composite: first the top at (0, 0), then left side at (0, 6), then right at (12, 6)
+    // for 24 pixel sprites
+    if faces.top
+        && let Some(top) = lit_face(BlockFace::Top, &plan.top)
     {
-        let cache = cache.block_sprite_cache.read().unwrap();
-        if let Some(img) = cache.get(&block_sprite_key) {
-            return img.clone();
-        }
+        overlay(&mut img, &top, 0, 0);
+    }
+    if faces.south
+        && let Some(side_left) = lit_face(BlockFace::South, &plan.south)
+    {
+        overlay(&mut img, &side_left, 0, size as i64 / 4);
+    }
+    if faces.east
+        && let Some(side_right) = lit_face(BlockFace::East, &plan.east)
+    {
+        overlay(&mut img, &side_right, size as i64 / 2, size as i64 / 4);
     }
-    // end cache read
 
-    // lock writeable cache
-    let mut block_sprite_cache = cache.block_sprite_cache.write().unwrap();
+    img
+}
 
-    let mut img = RgbaImage::new(SPRITE_SIZE, SPRITE_SIZE);
+/// Build a plant from two planes crossing in the middle of the block, one
+/// facing south and one facing east, lit by the brightest light around it
+/// Returns a square image of the sprite size
+pub fn render_block_cross(
+    cache: &AssetCache,
+    plan: &CrossSpritePlan,
+    light_data: &LightData,
+    tint: Option<[u8; 3]>,
+) -> RgbaImage {
+    let size = cache.sprite_size;
+    let mut img = RgbaImage::new(size, size);
 
-    // Read full-brightness block parts
-    let top_transformed = load_face(cache, BlockFace::Top, plan.face_top);
-    let side_left = load_face(cache, BlockFace::South, plan.face_south);
-    let side_right = load_face(cache, BlockFace::East, plan.face_east);
+    let plane = |face: BlockFace| {
+        build_face(cache, face, &plan.layers, tint)
+            .map(|face_img| darken_image(&face_img, light_data.brightest_factor()))
+    };
+    let (Some(south), Some(east)) = (plane(BlockFace::South), plane(BlockFace::East)) else {
+        return img;
+    };
 
-    // darken block faces
-    let top_transformed = darken_image(&top_transformed, light_data.factor(BlockFace::Top));
-    let side_left = darken_image(&side_left, light_data.factor(BlockFace::South));
-    let side_right = darken_image(&side_right, light_data.factor(BlockFace::East));
+    // Half a block back from the sides, the planes sit a quarter of the
+    // sprite across and an eighth down, at (6, 3) in 24 pixels. The left
+    // half of the east facing plane and the right half of the south facing
+    // one are in front of the other halves
+    let half = south.width() / 2;
+    let height = south.height();
+    let halves = [(&south, 0), (&east, half), (&east, 0), (&south, half)];
+    for (plane_img, x) in halves {
+        let part = crop_imm(plane_img, x, 0, half, height).to_image();
+        overlay(&mut img, &part, (size / 4 + x) as i64, size as i64 / 8);
+    }
 
-    // Composite: first the top at (0, 0), then left side at (0, 6), then right at (12, 6)
-    overlay(&mut img, &top_transformed, 0, 0);
-    overlay(&mut img, &side_left, 0, 6);
-    overlay(&mut img, &side_right, 12, 6);
+    img
+}
 
-    // write to cache
-    block_sprite_cache.insert(block_sprite_key, img.clone());
+/// The texture region covering a whole face
+pub const FULL_UV: [u8; 4] = [0, 0, 16, 16];
 
-    img
+/// A texture drawn on a face, over the layers before it
+#[derive(Debug, Clone)]
+pub struct FaceLayer {
+    pub texture: String,
+    /// Region of the texture as `[u1, v1, u2, v2]` in pixels of a 16 pixel
+    /// texture, stretched over the face
+    pub uv: [u8; 4],
+    /// Clockwise quarter turns of the texture region
+    pub turns: u8,
+    /// Takes the biome colour
+    pub tinted: bool,
+}
+
+impl FaceLayer {
+    /// A whole texture, upright
+    pub fn full(texture: String, tinted: bool) -> Self {
+        Self {
+            texture,
+            uv: FULL_UV,
+            turns: 0,
+            tinted,
+        }
+    }
 }
 
+/// Textures of the visible faces of a block, bottom layer first. Faces
+/// without layers are not drawn
+#[derive(Debug)]
 pub struct CubeSpritePlan {
-    pub face_east: String,
-    pub face_south: String,
-    pub face_top: String,
+    pub east: Vec<FaceLayer>,
+    pub south: Vec<FaceLayer>,
+    pub top: Vec<FaceLayer>,
+}
+
+/// Texture of a plant drawn as crossed planes, bottom layer first
+#[derive(Debug)]
+pub struct CrossSpritePlan {
+    pub layers: Vec<FaceLayer>,
 }
 
 /// Create a "missing texture" block (pink/black checkerboard)
@@ -169,3 +245,50 @@ fn create_missing_block_texture() -> RgbaImage {
     }
     tex
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture_animation::AnimationFrame;
+    use crate::texture_map::TextureMap;
+
+    #[test]
+    fn cross_planes_sit_in_the_middle_of_the_sprite() {
+        let light = LightData {
+            light_east: 15,
+            light_south: 15,
+            light_top: 15,
+        };
+        let plan = CrossSpritePlan {
+            layers: vec![FaceLayer::full("missing".to_string(), false)],
+        };
+        for size in [24, 48] {
+            let cache = AssetCache::new(
+                "/nonexistent",
+                TextureMap::bundled(),
+                AnimationFrame::Index(0),
+                Some(size),
+            );
+            let img = render_block_cross(&cache, &plan, &light, None);
+            assert_eq!(img.dimensions(), (size, size));
+            let drawn = |x, y| img.get_pixel(x, y)[3] > 0;
+
+            // The planes span half the sprite's width a quarter in from
+            // each side, from an eighth down to an eighth from the bottom
+            let (left, right) = (size / 4, size * 3 / 4 - 1);
+            let (top, bottom) = (size / 8, size * 7 / 8 - 1);
+            assert!(drawn(left, top) && drawn(right, top));
+            assert!(drawn(left, bottom) && drawn(right, bottom));
+            assert!(drawn(size / 2, size / 2));
+            // The planes meet lower in the middle than at their far ends
+            assert!(!drawn(size / 2, top));
+            assert!(!drawn(size / 2, bottom));
+            for y in 0..size {
+                assert!(!drawn(left - 1, y) && !drawn(right + 1, y));
+            }
+            for x in 0..size {
+                assert!(!drawn(x, top - 1) && !drawn(x, bottom + 1));
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::asset_cache::AssetCache;
+use crate::coords::block_face::VisibleFaces;
+use crate::light_data::LightData;
+use crate::render::render_shape::{ShapeSpritePlan, render_block_shape};
+use crate::section::PaletteEntry;
+use image::RgbaImage;
+
+/// Depth of water at which depth shading is strongest, in blocks
+pub const MAX_WATER_DEPTH: u8 = 16;
+
+/// Brightness of water at `MAX_WATER_DEPTH`
+const DEEPEST_WATER_FACTOR: f64 = 0.5;
+
+/// What the sprite of a fluid depends on besides its state and light
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct FluidContext {
+    /// More of the same fluid is above, filling the block to the top
+    pub covered: bool,
+    /// Blocks of water down to the first opaque block, when water is
+    /// shaded by its depth
+    pub depth: Option<u8>,
+}
+
+/// A fluid, drawn as a box up to its surface
+#[derive(Debug)]
+pub struct FluidSpritePlan {
+    /// The fluid with its surface lowered by its level
+    pub surface: ShapeSpritePlan,
+    /// The fluid filling the block, under more of it
+    pub full: ShapeSpritePlan,
+}
+
+/// Height of a fluid's surface in 16ths of a block. Sources and falling
+/// fluids are 8/9 full, and each level a fluid flows from its source lowers
+/// it by another ninth
+pub fn surface_height(state: &PaletteEntry) -> f32 {
+    let level = state
+        .property("level")
+        .and_then(|level| level.parse::<u8>().ok())
+        .unwrap_or(0);
+    let amount = if level >= 8 { 8 } else { 8 - level };
+    16.0 * amount as f32 / 9.0
+}
+
+/// Draw a fluid up to its surface, or to the top of the block when more of
+/// it is above. Deep water is darker and less see-through
+/// Returns a square image of the sprite size
+pub fn render_block_fluid(
+    cache: &AssetCache,
+    plan: &FluidSpritePlan,
+    faces: VisibleFaces,
+    light_data: &LightData,
+    tint: Option<[u8; 3]>,
+    context: FluidContext,
+) -> RgbaImage {
+    let shape = if context.covered {
+        &plan.full
+    } else {
+        &plan.surface
+    };
+    let mut img = render_block_shape(cache, shape, faces, light_data, tint);
+
+    if let Some(depth) = context.depth {
+        let deepness = depth.min(MAX_WATER_DEPTH) as f64 / MAX_WATER_DEPTH as f64;
+        let factor = 1.0 - deepness * (1.0 - DEEPEST_WATER_FACTOR);
+        for pixel in img.pixels_mut().filter(|pixel| pixel[3] > 0) {
+            for channel in 0..3 {
+                pixel[channel] = (pixel[channel] as f64 * factor) as u8;
+            }
+            pixel[3] += ((255 - pixel[3]) as f64 * deepness) as u8;
+        }
+    }
+
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn surface_falls_with_level() {
+        let fluid = |level: &str| PaletteEntry {
+            name: "minecraft:water".to_string(),
+            properties: BTreeMap::from([("level".to_string(), level.to_string())]),
+        };
+
+        assert_eq!(surface_height(&fluid("0")), 16.0 * 8.0 / 9.0);
+        assert_eq!(surface_height(&fluid("7")), 16.0 / 9.0);
+        assert!(surface_height(&fluid("3")) < surface_height(&fluid("2")));
+        // Falling fluid is as high as a source
+        assert_eq!(surface_height(&fluid("8")), surface_height(&fluid("0")));
+    }
+}
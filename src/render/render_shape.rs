@@ -0,0 +1,226 @@
+use crate::asset_cache::AssetCache;
+use crate::coords::block_face::{BlockFace, VisibleFaces};
+use crate::light_data::LightData;
+use crate::render::render_cube::{FaceLayer, layer_texture};
+use crate::render::transforms::face_shade;
+use image::{Pixel, Rgba, RgbaImage};
+
+/// Slack when testing whether a point is on a box, in blocks
+const EPSILON: f64 = 1e-6;
+
+/// A box of a block in 16ths of a block, with the textures of the faces
+/// the camera sees
+#[derive(Debug)]
+pub struct ShapeBox {
+    pub from: [f32; 3],
+    pub to: [f32; 3],
+    /// Layers of the east, south and top faces, bottom layer first
+    pub faces: Vec<(BlockFace, FaceLayer)>,
+}
+
+/// The boxes of a block that does not fill its cube, like slabs and fences
+#[derive(Debug)]
+pub struct ShapeSpritePlan {
+    pub boxes: Vec<ShapeBox>,
+}
+
+/// Draw the boxes of a block at their real size, keeping the pixel nearest
+/// the camera where they overlap. Faces on a side of the block that
+/// `faces` hides are left out, those inside the block are always drawn
+/// Returns a square image of the sprite size
+pub fn render_block_shape(
+    cache: &AssetCache,
+    plan: &ShapeSpritePlan,
+    faces: VisibleFaces,
+    light_data: &LightData,
+    tint: Option<[u8; 3]>,
+) -> RgbaImage {
+    let size = cache.sprite_size;
+    let mut img = RgbaImage::new(size, size);
+    // Nearness to the camera of the pixels drawn so far, `x + y + z`
+    let mut depth = vec![f64::NEG_INFINITY; (size * size) as usize];
+
+    for shape_box in &plan.boxes {
+        let from = shape_box.from.map(|c| c as f64 / 16.0);
+        let to = shape_box.to.map(|c| c as f64 / 16.0);
+        for (face, layer) in &shape_box.faces {
+            let (on_side, visible) = match face {
+                BlockFace::East => (to[0] >= 1.0, faces.east),
+                BlockFace::South => (to[2] >= 1.0, faces.south),
+                BlockFace::Top => (to[1] >= 1.0, faces.top),
+            };
+            if on_side && !visible {
+                continue;
+            }
+
+            let mut shade = [light_data.factor(*face) * face_shade(*face); 3];
+            if let Some(tint) = tint
+                && layer.tinted
+            {
+                shade = [0, 1, 2].map(|c| shade[c] * tint[c] as f64 / 255.0);
+            }
+            let texture = layer_texture(cache, layer);
+            draw_face(&mut img, &mut depth, *face, from, to, &texture, shade);
+        }
+    }
+
+    img
+}
+
+/// Draw one face of a box, with `from` and `to` in blocks. A point of the
+/// block is drawn at `(1 + x - z) / 2` across the sprite and
+/// `(x + z) / 4 + (1 - y) / 2` down it, in sprite sizes
+fn draw_face(
+    img: &mut RgbaImage,
+    depth: &mut [f64],
+    face: BlockFace,
+    from: [f64; 3],
+    to: [f64; 3],
+    texture: &RgbaImage,
+    shade: [f64; 3],
+) {
+    let [x1, y1, z1] = from;
+    let [x2, y2, z2] = to;
+    // Extent of the face along the texture's U and V
+    let (width, height) = match face {
+        BlockFace::East => (z2 - z1, y2 - y1),
+        BlockFace::South => (x2 - x1, y2 - y1),
+        BlockFace::Top => (x2 - x1, z2 - z1),
+    };
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+
+    let sprite_size = img.width();
+    let size = sprite_size as f64;
+    for py in 0..sprite_size {
+        for px in 0..sprite_size {
+            // The point of the face's plane under the pixel's centre
+            let across = (px as f64 + 0.5) / size;
+            let down = (py as f64 + 0.5) / size;
+            let [x, y, z] = match face {
+                BlockFace::East => {
+                    let z = x2 + 1.0 - 2.0 * across;
+                    [x2, 1.0 - 2.0 * (down - (x2 + z) / 4.0), z]
+                }
+                BlockFace::South => {
+                    let x = 2.0 * across - 1.0 + z2;
+                    [x, 1.0 - 2.0 * (down - (x + z2) / 4.0), z2]
+                }
+                BlockFace::Top => {
+                    let difference = 2.0 * across - 1.0;
+                    let sum = 4.0 * (down - (1.0 - y2) / 2.0);
+                    [(sum + difference) / 2.0, y2, (sum - difference) / 2.0]
+                }
+            };
+            let inside = [(x, x1, x2), (y, y1, y2), (z, z1, z2)]
+                .iter()
+                .all(|&(c, low, high)| c >= low - EPSILON && c <= high + EPSILON);
+            if !inside {
+                continue;
+            }
+
+            let index = (py * sprite_size + px) as usize;
+            let nearness = x + y + z;
+            if nearness < depth[index] - EPSILON {
+                continue;
+            }
+
+            // Position on the face along the texture's U and V, from 0 to 1
+            let (u, v) = match face {
+                BlockFace::East => ((z2 - z) / width, (y2 - y) / height),
+                BlockFace::South => ((x - x1) / width, (y2 - y) / height),
+                BlockFace::Top => ((x - x1) / width, (z - z1) / height),
+            };
+            let sample = |position: f64, length: u32| {
+                ((position * length as f64) as u32).min(length.saturating_sub(1))
+            };
+            let texel = texture.get_pixel(sample(u, texture.width()), sample(v, texture.height()));
+            if texel[3] == 0 {
+                continue;
+            }
+
+            let shaded = Rgba([
+                (texel[0] as f64 * shade[0]) as u8,
+                (texel[1] as f64 * shade[1]) as u8,
+                (texel[2] as f64 * shade[2]) as u8,
+                texel[3],
+            ]);
+            img.get_pixel_mut(px, py).blend(&shaded);
+            depth[index] = nearness;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture_animation::AnimationFrame;
+    use crate::texture_map::TextureMap;
+
+    fn shape(boxes: &[([f32; 3], [f32; 3])]) -> ShapeSpritePlan {
+        let sides = [BlockFace::East, BlockFace::South, BlockFace::Top];
+        ShapeSpritePlan {
+            boxes: boxes
+                .iter()
+                .map(|&(from, to)| ShapeBox {
+                    from,
+                    to,
+                    faces: sides
+                        .iter()
+                        .map(|&face| (face, FaceLayer::full("missing".to_string(), false)))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn boxes_are_drawn_at_their_size() {
+        let cache = AssetCache::new(
+            "/nonexistent",
+            TextureMap::bundled(),
+            AnimationFrame::Index(0),
+            Some(24),
+        );
+        let all = VisibleFaces {
+            east: true,
+            south: true,
+            top: true,
+        };
+        let light = LightData {
+            light_east: 15,
+            light_south: 15,
+            light_top: 15,
+        };
+        let drawn = |img: &RgbaImage, x, y| img.get_pixel(x, y)[3] > 0;
+
+        let full = render_block_shape(&cache, &shape(&[([0.0; 3], [16.0; 3])]), all, &light, None);
+        assert!(drawn(&full, 12, 1));
+        assert!(drawn(&full, 12, 22));
+        assert!(drawn(&full, 1, 17));
+
+        // A bottom slab leaves the upper half of the sprite empty
+        let slab = shape(&[([0.0; 3], [16.0, 8.0, 16.0])]);
+        let img = render_block_shape(&cache, &slab, all, &light, None);
+        assert!(!drawn(&img, 12, 1));
+        assert!(drawn(&img, 12, 7));
+        assert!(drawn(&img, 12, 22));
+
+        // The sides of a post in the middle are never hidden
+        let post = shape(&[([6.0, 0.0, 6.0], [10.0, 16.0, 10.0])]);
+        let hidden = VisibleFaces {
+            east: false,
+            south: false,
+            top: false,
+        };
+        let img = render_block_shape(&cache, &post, hidden, &light, None);
+        assert!(drawn(&img, 12, 12));
+        assert!(!drawn(&img, 1, 17));
+
+        // The missing texture was looked for once, and its layer cut once
+        let textures = cache.texture_cache.read().unwrap();
+        assert!(matches!(textures.get("missing"), Some(None)));
+        assert_eq!(cache.layer_cache.read().unwrap().len(), 1);
+    }
+}
@@ -1,53 +1,416 @@
 use crate::light_data::LightData;
 use crate::render::mode::RenderMode;
-use crate::render::render_cube::{CubeSpritePlan, render_block_3d};
+use crate::render::render_cube::{
+    CrossSpritePlan, CubeSpritePlan, FaceLayer, render_block_3d, render_block_cross,
+};
+use crate::render::render_fluid::{
+    FluidContext, FluidSpritePlan, MAX_WATER_DEPTH, render_block_fluid, surface_height,
+};
+use crate::render::render_shape::{ShapeBox, ShapeSpritePlan, render_block_shape};
+use crate::section::PaletteEntry;
 use crate::{
-    asset_cache::AssetCache,
-    blocks::is_air_block,
+    asset_cache::{AssetCache, BlockSpriteKey},
+    biome::DEFAULT_BIOME,
+    block_model::{Direction, ModelElement, PlacedModel, default_uv},
+    block_registry::BlockInfo,
+    blocks::{Shape, Transparency},
     chunk_store::ChunkStore,
     coords::{
-        constants::MC_CHUNK_SIZE, painters_range::PaintersRange, world_block_coord::WorldBlockCoord,
+        block_face::{BlockFace, VisibleFaces},
+        constants::MC_CHUNK_SIZE,
+        painters_range::PaintersRange,
+        world_block_coord::WorldBlockCoord,
         world_chunk_coord::WorldChunkCoord,
     },
+    utils::{cut_out, darken_image},
 };
 use image::{RgbaImage, imageops::overlay};
 use rayon::prelude::*;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Brightness of partially generated chunks when they are dimmed
+const PARTIAL_CHUNK_FACTOR: f64 = 0.5;
+
+/// The faces of a block sprite, in the order they are planned
+const SPRITE_FACES: [BlockFace; 3] = [BlockFace::East, BlockFace::South, BlockFace::Top];
+
+/// How a block state is drawn
+#[derive(Debug)]
+pub enum SpritePlan {
+    /// A full cube from the sprite's three face textures
+    Cube(CubeSpritePlan),
+    /// Boxes drawn at their real size
+    Shape(ShapeSpritePlan),
+    /// A plant's crossed planes
+    Cross(CrossSpritePlan),
+    /// A fluid up to its surface
+    Fluid(FluidSpritePlan),
+}
 
-pub const SPRITE_SIZE: u32 = 24;
+/// Get or create a rendered block sprite for an interned block state
+pub fn get_block_sprite(
+    cache: &AssetCache,
+    block: &BlockInfo,
+    faces: VisibleFaces,
+    light_data: LightData,
+    tint: Option<[u8; 3]>,
+    fluid: FluidContext,
+) -> RgbaImage {
+    let key = BlockSpriteKey {
+        light: light_data,
+        block: block.id,
+        faces,
+        tint,
+        fluid,
+    };
+    if let Some(img) = cache.block_sprite_cache.read().unwrap().get(&key) {
+        return img.clone();
+    }
 
-/// Get or create a rendered block sprite for a block name
-/// Block name should be like "minecraft:stone" or "minecraft:grass_block"
-pub fn get_block_sprite(cache: &AssetCache, block_name: &str, light_data: LightData) -> RgbaImage {
-    let name = block_name.strip_prefix("minecraft:").unwrap_or(block_name);
-    let sprite = create_block_sprite(cache, name, light_data);
-    sprite
+    let img = create_block_sprite(cache, block, faces, &key.light, tint, fluid);
+    cache
+        .block_sprite_cache
+        .write()
+        .unwrap()
+        .insert(key, img.clone());
+    img
 }
 
-/// Translate a palette block name into 3 texture names
-fn plan_block_sprite(block_name: &str) -> Option<CubeSpritePlan> {
-    let name = block_name.strip_prefix("minecraft:").unwrap_or(block_name);
+/// Textures of a block state's faces, planned once per block state
+fn get_block_plan(cache: &AssetCache, block: &BlockInfo) -> Option<Arc<SpritePlan>> {
+    if let Some(plan) = cache.block_plan_cache.read().unwrap().get(&block.id) {
+        return plan.clone();
+    }
+    let plan = plan_block_sprite(cache, block).map(Arc::new);
+    cache
+        .block_plan_cache
+        .write()
+        .unwrap()
+        .insert(block.id, plan.clone());
+    plan
+}
 
-    // TODO: Cache block sprite plans?
+/// Translate a block state into the textures of its faces, from its models
+/// when the assets have them and otherwise from its name and shape
+fn plan_block_sprite(cache: &AssetCache, block: &BlockInfo) -> Option<SpritePlan> {
+    if block.properties.is_invisible() {
+        return None;
+    }
+
+    let models = cache.models.models_for(&block.name, &block.state);
+    if let Some(plan) = plan_from_models(&models) {
+        return Some(plan);
+    }
 
-    if name == "air" || name == "cave_air" || name == "void_air" {
+    let name = block.name.as_str();
+    let state = &block.state;
+    let face_layers = |face: BlockFace| {
+        let texture = cache.find_texture(&face_texture_name(name, state, face), &face);
+        match &block.properties.side_overlay {
+            Some(overlay) if face != BlockFace::Top => vec![
+                FaceLayer::full(texture, false),
+                FaceLayer::full(cache.find_texture(overlay, &face), true),
+            ],
+            _ => vec![FaceLayer::full(texture, true)],
+        }
+    };
+    // Each box shows the part of the textures under it
+    let shape_box = |from: [f32; 3], to: [f32; 3]| ShapeBox {
+        from,
+        to,
+        faces: SPRITE_FACES
+            .into_iter()
+            .flat_map(|face| {
+                let uv = uv_pixels(default_uv(from, to, Direction::from(&face)));
+                face_layers(face)
+                    .into_iter()
+                    .map(move |layer| (face, FaceLayer { uv, ..layer }))
+            })
+            .collect(),
+    };
+
+    if block.properties.shape == Shape::Fluid {
+        let height = surface_height(state);
+        return Some(SpritePlan::Fluid(FluidSpritePlan {
+            surface: ShapeSpritePlan {
+                boxes: vec![shape_box([0.0; 3], [16.0, height, 16.0])],
+            },
+            full: ShapeSpritePlan {
+                boxes: vec![shape_box([0.0; 3], [16.0; 3])],
+            },
+        }));
+    }
+
+    if block.properties.shape == Shape::Cross {
+        let texture = face_texture_name(name, state, BlockFace::South);
+        return Some(SpritePlan::Cross(CrossSpritePlan {
+            layers: vec![FaceLayer::full(
+                cache.find_texture(&texture, &BlockFace::South),
+                true,
+            )],
+        }));
+    }
+
+    let Some(boxes) = shape_boxes(block.properties.shape, state) else {
+        return Some(SpritePlan::Cube(CubeSpritePlan {
+            east: face_layers(BlockFace::East),
+            south: face_layers(BlockFace::South),
+            top: face_layers(BlockFace::Top),
+        }));
+    };
+    let boxes = boxes
+        .into_iter()
+        .map(|(from, to)| shape_box(from, to))
+        .collect();
+    Some(SpritePlan::Shape(ShapeSpritePlan { boxes }))
+}
+
+/// Plan a block from its models. Models of whole cubes are drawn like
+/// cubes, those of plants as crossed planes and others box by box. Fluids
+/// and blocks drawn like entities have models without elements, and are
+/// planned by name instead
+fn plan_from_models(models: &[PlacedModel]) -> Option<SpritePlan> {
+    if models.iter().all(|model| model.elements.is_empty()) {
         return None;
     }
 
-    Some(CubeSpritePlan {
-        face_east: name.to_string(),
-        face_south: name.to_string(),
-        face_top: name.to_string(),
+    // Both planes of a cross show the same texture
+    let mut elements = models.iter().flat_map(|model| &model.elements);
+    if elements.clone().all(ModelElement::is_cross_plane) {
+        let layer = elements.next().and_then(|element| {
+            let (direction, element_face) = Direction::ALL
+                .into_iter()
+                .find_map(|direction| Some((direction, element.faces.get(&direction)?)))?;
+            Some(FaceLayer {
+                texture: element_face.texture.clone(),
+                uv: uv_pixels(element.face_uv(direction)),
+                turns: (element_face.rotation.rem_euclid(360) / 90) as u8,
+                tinted: element_face.tintindex.is_some(),
+            })
+        });
+        return Some(SpritePlan::Cross(CrossSpritePlan {
+            layers: layer.into_iter().collect(),
+        }));
+    }
+
+    if models
+        .iter()
+        .flat_map(|model| &model.elements)
+        .all(ModelElement::is_full_cube)
+    {
+        let face_layers = |face: BlockFace| {
+            models
+                .iter()
+                .flat_map(|model| {
+                    model
+                        .elements
+                        .iter()
+                        .filter_map(move |element| model_layer(model, element, face))
+                })
+                .collect()
+        };
+        return Some(SpritePlan::Cube(CubeSpritePlan {
+            east: face_layers(BlockFace::East),
+            south: face_layers(BlockFace::South),
+            top: face_layers(BlockFace::Top),
+        }));
+    }
+
+    let boxes = models
+        .iter()
+        .flat_map(|model| {
+            model.elements.iter().map(move |element| {
+                let (from, to) = model.rotate_box(element.from, element.to);
+                ShapeBox {
+                    from,
+                    to,
+                    faces: SPRITE_FACES
+                        .into_iter()
+                        .filter_map(|face| Some((face, model_layer(model, element, face)?)))
+                        .collect(),
+                }
+            })
+        })
+        .collect();
+    Some(SpritePlan::Shape(ShapeSpritePlan { boxes }))
+}
+
+/// The texture an element of a placed model shows on a face of the sprite
+fn model_layer(model: &PlacedModel, element: &ModelElement, face: BlockFace) -> Option<FaceLayer> {
+    let model_face = model.model_face(Direction::from(&face));
+    let element_face = element.faces.get(&model_face)?;
+    let turns = model.texture_turns(model_face) as i32 + element_face.rotation.rem_euclid(360) / 90;
+    Some(FaceLayer {
+        texture: element_face.texture.clone(),
+        uv: uv_pixels(element.face_uv(model_face)),
+        turns: (turns % 4) as u8,
+        tinted: element_face.tintindex.is_some(),
     })
 }
 
-/// Create a block sprite from a block name
-fn create_block_sprite(cache: &AssetCache, name: &str, light_data: LightData) -> RgbaImage {
-    let cube_plan = plan_block_sprite(name);
+/// A texture region in 16ths as whole pixels of a 16 pixel texture
+fn uv_pixels(uv: [f32; 4]) -> [u8; 4] {
+    uv.map(|c| c.round().clamp(0.0, 16.0) as u8)
+}
 
-    match cube_plan {
-        None => RgbaImage::new(SPRITE_SIZE, SPRITE_SIZE),
-        Some(cube_plan) => render_block_3d(cache, name, cube_plan, light_data),
+/// Boxes of the shapes drawn without models, in 16ths of a block, or none
+/// for blocks drawn as cubes. Stairs are drawn straight and walls and
+/// fences connect by their state like in the game
+fn shape_boxes(shape: Shape, state: &PaletteEntry) -> Option<Vec<([f32; 3], [f32; 3])>> {
+    const SIDES: [&str; 4] = ["north", "south", "west", "east"];
+    let connected = |values: &[&str]| {
+        SIDES
+            .into_iter()
+            .filter(|side| state.property(side).is_some_and(|v| values.contains(&v)))
+            .collect::<Vec<_>>()
+    };
+
+    let boxes = match shape {
+        Shape::Slab => match state.property("type") {
+            Some("double") => return None,
+            Some("top") => vec![([0.0, 8.0, 0.0], [16.0; 3])],
+            _ => vec![([0.0; 3], [16.0, 8.0, 16.0])],
+        },
+        Shape::Stairs => {
+            let (base, step) = match state.property("half") {
+                Some("top") => ((8.0, 16.0), (0.0, 8.0)),
+                _ => ((0.0, 8.0), (8.0, 16.0)),
+            };
+            let (step_from, step_to) = match state.property("facing") {
+                Some("north") => ([0.0, step.0, 0.0], [16.0, step.1, 8.0]),
+                Some("west") => ([0.0, step.0, 0.0], [8.0, step.1, 16.0]),
+                Some("east") => ([8.0, step.0, 0.0], [16.0, step.1, 16.0]),
+                _ => ([0.0, step.0, 8.0], [16.0, step.1, 16.0]),
+            };
+            vec![
+                ([0.0, base.0, 0.0], [16.0, base.1, 16.0]),
+                (step_from, step_to),
+            ]
+        }
+        Shape::Fence => {
+            let mut boxes = vec![([6.0, 0.0, 6.0], [10.0, 16.0, 10.0])];
+            for side in connected(&["true"]) {
+                boxes.push(arm(side, 2.0, (6.0, 9.0)));
+                boxes.push(arm(side, 2.0, (12.0, 15.0)));
+            }
+            boxes
+        }
+        Shape::Wall => {
+            let mut boxes = Vec::new();
+            if state.property("up") != Some("false") {
+                boxes.push(([4.0, 0.0, 4.0], [12.0, 16.0, 12.0]));
+            }
+            for side in connected(&["low", "true"]) {
+                boxes.push(arm(side, 6.0, (0.0, 14.0)));
+            }
+            for side in connected(&["tall"]) {
+                boxes.push(arm(side, 6.0, (0.0, 16.0)));
+            }
+            boxes
+        }
+        Shape::Pane => {
+            let mut boxes = vec![([7.0, 0.0, 7.0], [9.0, 16.0, 9.0])];
+            for side in connected(&["true"]) {
+                boxes.push(arm(side, 2.0, (0.0, 16.0)));
+            }
+            boxes
+        }
+        _ => return None,
+    };
+    Some(boxes)
+}
+
+/// A box from the middle of the block to one side, `width` across and
+/// spanning `y`
+fn arm(side: &str, width: f32, y: (f32, f32)) -> ([f32; 3], [f32; 3]) {
+    let (near, far) = (8.0 - width / 2.0, 8.0 + width / 2.0);
+    match side {
+        "north" => ([near, y.0, 0.0], [far, y.1, 8.0]),
+        "south" => ([near, y.0, 8.0], [far, y.1, 16.0]),
+        "west" => ([0.0, y.0, near], [8.0, y.1, far]),
+        _ => ([8.0, y.0, near], [16.0, y.1, far]),
+    }
+}
+
+/// Texture name for one face of a block, for blocks whose textures depend on
+/// their state. Other names are mapped to textures by `AssetCache::find_texture`
+fn face_texture_name(name: &str, state: &PaletteEntry, face: BlockFace) -> String {
+    // Logs and stems show their rings on the faces their axis passes through
+    if let Some(axis) = state.property("axis")
+        && (name.ends_with("_log") || name.ends_with("_stem"))
+    {
+        let end_face = match axis {
+            "x" => BlockFace::East,
+            "z" => BlockFace::South,
+            _ => BlockFace::Top,
+        };
+        return if face == end_face {
+            format!("{}_top", name)
+        } else {
+            name.to_string()
+        };
+    }
+
+    // Doors and two block tall plants have a texture for each half
+    match state.property("half") {
+        Some("upper") => return format!("{}_top", name),
+        Some("lower") => return format!("{}_bottom", name),
+        _ => {}
+    }
+
+    // Crops have a texture per growth stage
+    if let Some(age) = state
+        .property("age")
+        .and_then(|age| age.parse::<usize>().ok())
+    {
+        match name {
+            "wheat" | "beetroots" | "sweet_berry_bush" | "torchflower_crop" => {
+                return format!("{}_stage{}", name, age);
+            }
+            // Eight ages share four textures
+            "carrots" | "potatoes" => {
+                let stage = [0, 0, 1, 1, 2, 2, 2, 3][age.min(7)];
+                return format!("{}_stage{}", name, stage);
+            }
+            // Four ages share three textures
+            "nether_wart" => {
+                let stage = [0, 1, 1, 2][age.min(3)];
+                return format!("{}_stage{}", name, stage);
+            }
+            _ => {}
+        }
+    }
+
+    name.to_string()
+}
+
+/// Create a block sprite from a block state
+fn create_block_sprite(
+    cache: &AssetCache,
+    block: &BlockInfo,
+    faces: VisibleFaces,
+    light_data: &LightData,
+    tint: Option<[u8; 3]>,
+    fluid: FluidContext,
+) -> RgbaImage {
+    let mut img = match get_block_plan(cache, block).as_deref() {
+        None => RgbaImage::new(cache.sprite_size, cache.sprite_size),
+        Some(SpritePlan::Cube(plan)) => render_block_3d(cache, plan, faces, light_data, tint),
+        Some(SpritePlan::Shape(plan)) => render_block_shape(cache, plan, faces, light_data, tint),
+        Some(SpritePlan::Cross(plan)) => render_block_cross(cache, plan, light_data, tint),
+        Some(SpritePlan::Fluid(plan)) => {
+            render_block_fluid(cache, plan, faces, light_data, tint, fluid)
+        }
+    };
+
+    // Translucent blocks keep the alpha of their textures, blending with
+    // what is behind them. Others are cut out, showing what is behind only
+    // through their holes
+    if block.properties.transparency != Transparency::Translucent {
+        cut_out(&mut img);
     }
+    img
 }
 
 /// Render multiple chunks in a grid
@@ -67,11 +430,13 @@ pub fn render_world(
 
     let total_height = max_y - min_y + 1;
 
-    // Calculate output image size
-    let xz_area_factor = MC_CHUNK_SIZE * (chunk_width_x + chunk_width_z) * 12;
-    let y_area_factor = total_height * 12;
+    // Calculate output image size. Each block is half a sprite wide along
+    // X and Z, and stacks half a sprite higher
+    let sprite_size = cache.sprite_size as isize;
+    let xz_area_factor = MC_CHUNK_SIZE * (chunk_width_x + chunk_width_z) * sprite_size / 2;
+    let y_area_factor = total_height * sprite_size / 2;
     let width = xz_area_factor as u32;
-    let height = (xz_area_factor + y_area_factor + 24) as u32;
+    let height = (xz_area_factor + y_area_factor + sprite_size) as u32;
 
     println!(
         "Rendering world region: chunks ({}) to ({})",
@@ -92,35 +457,33 @@ pub fn render_world(
     // - Diagonal slices from back (high x+z) to front (low x+z)
 
     let chunk_coords: Vec<WorldChunkCoord> = chunk_min.painters_range_to(chunk_max).collect();
+    let world = StoreView { store, mode, max_y };
     let chunk_renders: Vec<ChunkRenderResult> = chunk_coords
         .par_iter()
         .map(|chunk_coord| {
-            render_chunk(
-                cache,
-                |coords| store.get_block_at(coords),
-                |coords| {
-                    if mode.night {
-                        store.get_block_light_at(coords)
-                    } else {
-                        let block_light = store.get_block_light_at(coords).unwrap_or(0);
-                        Some(store.get_sky_light_at(coords).max(block_light))
-                    }
-                },
-                *chunk_coord,
-                min_y,
-                max_y,
-            )
+            let mut chunk_render = render_chunk(cache, &world, *chunk_coord, min_y..max_y);
+
+            let partial = store
+                .chunks
+                .get(chunk_coord)
+                .is_some_and(|chunk| !chunk.is_fully_generated());
+            if world.mode.dim_partial_chunks && partial {
+                chunk_render.img = darken_image(&chunk_render.img, PARTIAL_CHUNK_FACTOR);
+            }
+            chunk_render
         })
         .collect();
 
     for chunk_render in chunk_renders {
         let chunk_pos = img_coords(
+            cache.sprite_size,
             chunk_render.img.width(),
             chunk_render.coord.world_block_coord_min(min_y),
             chunk_render.coord.world_block_coord_max(max_y),
             chunk_render.coord.world_block_coord_min(min_y),
         );
         let screen_pos = img_coords(
+            cache.sprite_size,
             img.width(),
             chunk_min.world_block_coord_min(min_y),
             chunk_max.world_block_coord_max(max_y),
@@ -141,23 +504,70 @@ pub fn render_world(
     img
 }
 
+/// The world data a chunk is drawn from, looked up block by block
+trait WorldView {
+    fn block(&self, coords: &WorldBlockCoord) -> Option<&BlockInfo>;
+    fn biome(&self, coords: &WorldBlockCoord) -> Option<&str>;
+    /// Lowest and highest Y worth drawing in a column, `None` when every
+    /// block of it is drawn
+    fn visible_range(&self, column: &WorldBlockCoord) -> Option<(isize, isize)>;
+    /// Depth of the water a block is in, when water is shaded by its depth
+    fn water_depth(&self, coords: &WorldBlockCoord) -> Option<u8>;
+    fn light(&self, coords: &WorldBlockCoord) -> Option<u8>;
+}
+
+/// The loaded chunks seen through a render mode, up to `max_y` (exclusive)
+struct StoreView<'a> {
+    store: &'a ChunkStore,
+    mode: RenderMode,
+    max_y: isize,
+}
+
+impl WorldView for StoreView<'_> {
+    fn block(&self, coords: &WorldBlockCoord) -> Option<&BlockInfo> {
+        self.store.get_block_info_at(coords)
+    }
+
+    fn biome(&self, coords: &WorldBlockCoord) -> Option<&str> {
+        self.store.get_biome_at(coords)
+    }
+
+    fn visible_range(&self, column: &WorldBlockCoord) -> Option<(isize, isize)> {
+        self.store.get_visible_y_range(column, self.max_y)
+    }
+
+    fn water_depth(&self, coords: &WorldBlockCoord) -> Option<u8> {
+        self.mode
+            .water_depth
+            .then(|| self.store.get_water_depth_at(coords, MAX_WATER_DEPTH))
+    }
+
+    fn light(&self, coords: &WorldBlockCoord) -> Option<u8> {
+        if self.mode.night {
+            self.store.get_block_light_at(coords)
+        } else {
+            let block_light = self.store.get_block_light_at(coords).unwrap_or(0);
+            Some(self.store.get_sky_light_at(coords).max(block_light))
+        }
+    }
+}
+
 struct ChunkRenderResult {
     coord: WorldChunkCoord,
     img: RgbaImage,
 }
 
-fn render_chunk<F, FL>(
+fn render_chunk(
     cache: &AssetCache,
-    mut get_block: F,
-    mut get_light: FL,
+    world: &impl WorldView,
     chunk_coord: WorldChunkCoord,
-    min_y: isize,
-    max_y: isize,
-) -> ChunkRenderResult
-where
-    F: FnMut(&WorldBlockCoord) -> Option<String>,
-    FL: FnMut(&WorldBlockCoord) -> Option<u8>,
-{
+    y_range: Range<isize>,
+) -> ChunkRenderResult {
+    let Range {
+        start: min_y,
+        end: max_y,
+    } = y_range;
+
     // Calculate world coordinate ranges
     let world_min = chunk_coord.world_block_coord_min(min_y);
     let world_max = chunk_coord.world_block_coord_max(max_y);
@@ -165,25 +575,89 @@ where
     let total_height = max_y - min_y + 1;
 
     // Calculate output image size
-    let width = (MC_CHUNK_SIZE * 24) as u32;
-    let height = (MC_CHUNK_SIZE * 12 + total_height * 12 + 24) as u32;
+    let sprite_size = cache.sprite_size as isize;
+    let width = (MC_CHUNK_SIZE * sprite_size) as u32;
+    let height =
+        (MC_CHUNK_SIZE * sprite_size / 2 + total_height * sprite_size / 2 + sprite_size) as u32;
 
     let mut img = RgbaImage::new(width, height);
 
+    // Visible Y range of each column, `z * 16 + x`, to skip buried blocks
+    let visible_ranges: Vec<Option<(isize, isize)>> = (0..MC_CHUNK_SIZE * MC_CHUNK_SIZE)
+        .map(|index| {
+            world.visible_range(&WorldBlockCoord {
+                x: world_min.x + index % MC_CHUNK_SIZE,
+                y: world_min.y,
+                z: world_min.z + index / MC_CHUNK_SIZE,
+            })
+        })
+        .collect();
+
     for block_coord in world_min.painters_range_to(&world_max) {
-        if let Some(block_name) = get_block(&block_coord) {
-            if !is_air_block(&block_name) {
-                let light_info = LightData {
-                    light_top: get_light(&block_coord.top_pos_y()).unwrap_or(0),
-                    light_east: get_light(&block_coord.east_pos_x()).unwrap_or(0),
-                    light_south: get_light(&block_coord.south_pos_z()).unwrap_or(0),
-                };
-
-                let sprite = get_block_sprite(cache, &block_name, light_info);
-
-                let screen_pos = img_coords(width, world_min, world_max, block_coord);
-                overlay(&mut img, &sprite, screen_pos.0 as i64, screen_pos.1 as i64);
+        let column = (block_coord.z - world_min.z) * MC_CHUNK_SIZE + block_coord.x - world_min.x;
+        if let Some((lowest, highest)) = visible_ranges[column as usize]
+            && (block_coord.y < lowest || block_coord.y > highest)
+        {
+            continue;
+        }
+
+        let Some(block) = world.block(&block_coord) else {
+            continue;
+        };
+        // Waterlogged blocks are drawn inside the water around them
+        for block in std::iter::once(block).chain(block.water.as_deref()) {
+            if block.properties.is_invisible() {
+                continue;
             }
+
+            // Faces against a covering neighbour are hidden. Neighbours that
+            // are not loaded or above the cut leave the face open
+            let top_pos_y = block_coord.top_pos_y();
+            let east_pos_x = block_coord.east_pos_x();
+            let south_pos_z = block_coord.south_pos_z();
+            let is_covered = |coord: &WorldBlockCoord| {
+                world
+                    .block(coord)
+                    .is_some_and(|neighbour| neighbour.hides_face_of(block))
+            };
+            let faces = VisibleFaces {
+                top: block_coord.y == world_max.y || !is_covered(&top_pos_y),
+                east: !is_covered(&east_pos_x),
+                south: !is_covered(&south_pos_z),
+            };
+            if faces.is_empty() {
+                continue;
+            }
+
+            // Blocks that give off light are lit at least by their own
+            let face_light = |visible: bool, coord: &WorldBlockCoord| {
+                if visible {
+                    world
+                        .light(coord)
+                        .unwrap_or(0)
+                        .max(block.properties.emission)
+                } else {
+                    0
+                }
+            };
+            let light_info = LightData {
+                light_top: face_light(faces.top, &top_pos_y),
+                light_east: face_light(faces.east, &east_pos_x),
+                light_south: face_light(faces.south, &south_pos_z),
+            };
+
+            let tint = block.properties.tint.map(|tint| {
+                let biome = world.biome(&block_coord).unwrap_or(DEFAULT_BIOME);
+                cache.colormaps.tint_color(tint, biome)
+            });
+
+            let fluid = fluid_context(world, block, &block_coord);
+
+            let sprite = get_block_sprite(cache, block, faces, light_info, tint, fluid);
+
+            let screen_pos =
+                img_coords(cache.sprite_size, width, world_min, world_max, block_coord);
+            overlay(&mut img, &sprite, screen_pos.0 as i64, screen_pos.1 as i64);
         }
     }
 
@@ -193,7 +667,32 @@ where
     }
 }
 
+/// Fluids fill their block under more of themselves, and water may be
+/// shaded by its depth
+fn fluid_context(
+    world: &impl WorldView,
+    block: &BlockInfo,
+    block_coord: &WorldBlockCoord,
+) -> FluidContext {
+    if block.properties.shape != Shape::Fluid {
+        return FluidContext::default();
+    }
+    FluidContext {
+        covered: world
+            .block(&block_coord.top_pos_y())
+            .is_some_and(|above| above.holds_fluid_of(block)),
+        depth: if block.is_waterlogged() {
+            world.water_depth(block_coord)
+        } else {
+            None
+        },
+    }
+}
+
+/// Position in an image of the sprite of a block, for sprites of
+/// `sprite_size` pixels
 fn img_coords(
+    sprite_size: u32,
     img_width: u32,
     world_min: WorldBlockCoord,
     world_max: WorldBlockCoord,
@@ -206,9 +705,221 @@ fn img_coords(
     let rel_x = anchor_coord.x - world_min.x;
     let rel_z = anchor_coord.z - world_min.z;
 
-    let screen_x = ((rel_x - rel_z) * 12 + (img_width as isize / 2) - 12) as u32;
-    let screen_y =
-        ((rel_x + rel_z) * 6 - (anchor_coord.y - world_min.y) * 12 + (total_height * 12)) as u32;
+    let half = sprite_size as isize / 2;
+    let quarter = sprite_size as isize / 4;
+    let screen_x = ((rel_x - rel_z) * half + (img_width as isize / 2) - half) as u32;
+    let screen_y = ((rel_x + rel_z) * quarter - (anchor_coord.y - world_min.y) * half
+        + (total_height * half)) as u32;
 
     (screen_x, screen_y)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::block_model::test_util::{models, state};
+    use crate::block_registry::{BlockId, BlockRegistry};
+    use crate::blocks::BlockMetadata;
+
+    /// Blocks placed by hand in a column at X 0, Z 0, with every water
+    /// block `DEPTH` deep
+    struct ColumnView {
+        registry: BlockRegistry,
+        blocks: HashMap<isize, BlockId>,
+    }
+
+    impl ColumnView {
+        const DEPTH: u8 = 5;
+
+        fn new(states: &[(isize, PaletteEntry)]) -> Self {
+            let mut registry = BlockRegistry::new(BlockMetadata::bundled());
+            let blocks = states
+                .iter()
+                .map(|(y, state)| (*y, registry.intern(state)))
+                .collect();
+            Self { registry, blocks }
+        }
+
+        fn at(y: isize) -> WorldBlockCoord {
+            WorldBlockCoord { x: 0, y, z: 0 }
+        }
+    }
+
+    impl WorldView for ColumnView {
+        fn block(&self, coords: &WorldBlockCoord) -> Option<&BlockInfo> {
+            let id = self.blocks.get(&coords.y)?;
+            Some(self.registry.get(*id))
+        }
+
+        fn biome(&self, _coords: &WorldBlockCoord) -> Option<&str> {
+            None
+        }
+
+        fn visible_range(&self, _column: &WorldBlockCoord) -> Option<(isize, isize)> {
+            None
+        }
+
+        fn water_depth(&self, _coords: &WorldBlockCoord) -> Option<u8> {
+            Some(Self::DEPTH)
+        }
+
+        fn light(&self, _coords: &WorldBlockCoord) -> Option<u8> {
+            None
+        }
+    }
+
+    const CROSS: &str = r##"{
+        "elements": [
+            { "from": [0.8, 0, 8], "to": [15.2, 16, 8],
+              "rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true },
+              "faces": {
+                  "north": { "uv": [0, 0, 16, 16], "texture": "#cross" },
+                  "south": { "uv": [0, 0, 16, 16], "texture": "#cross" } } },
+            { "from": [8, 0, 0.8], "to": [8, 16, 15.2],
+              "rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true },
+              "faces": {
+                  "west": { "uv": [0, 0, 16, 16], "texture": "#cross" },
+                  "east": { "uv": [0, 0, 16, 16], "texture": "#cross" } } }
+        ]
+    }"##;
+
+    const TINTED_CROSS: &str = r##"{
+        "elements": [
+            { "from": [0.8, 0, 8], "to": [15.2, 16, 8],
+              "rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true },
+              "faces": {
+                  "north": { "uv": [0, 0, 16, 16], "texture": "#cross", "tintindex": 0 },
+                  "south": { "uv": [0, 0, 16, 16], "texture": "#cross", "tintindex": 0 } } },
+            { "from": [8, 0, 0.8], "to": [8, 16, 15.2],
+              "rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true },
+              "faces": {
+                  "west": { "uv": [0, 0, 16, 16], "texture": "#cross", "tintindex": 0 },
+                  "east": { "uv": [0, 0, 16, 16], "texture": "#cross", "tintindex": 0 } } }
+        ]
+    }"##;
+
+    #[test]
+    fn plant_models_are_planned_as_crosses() {
+        let models = models(
+            &[
+                (
+                    "poppy",
+                    r#"{ "variants": { "": { "model": "minecraft:block/poppy" } } }"#,
+                ),
+                (
+                    "short_grass",
+                    r#"{ "variants": { "": { "model": "minecraft:block/short_grass" } } }"#,
+                ),
+            ],
+            &[
+                ("minecraft:block/cross", CROSS),
+                ("minecraft:block/tinted_cross", TINTED_CROSS),
+                (
+                    "minecraft:block/poppy",
+                    r#"{ "parent": "minecraft:block/cross", "textures": { "cross": "minecraft:block/poppy" } }"#,
+                ),
+                (
+                    "minecraft:block/short_grass",
+                    r#"{ "parent": "block/tinted_cross", "textures": { "cross": "block/short_grass" } }"#,
+                ),
+            ],
+        );
+        let plan = |name: &str| {
+            plan_from_models(&models.models_for(name, &state(&format!("minecraft:{}", name), &[])))
+        };
+
+        let Some(SpritePlan::Cross(poppy)) = plan("poppy") else {
+            panic!("poppy is not planned as a cross");
+        };
+        assert_eq!(poppy.layers.len(), 1);
+        assert_eq!(poppy.layers[0].texture, "poppy");
+        assert!(!poppy.layers[0].tinted);
+
+        let Some(SpritePlan::Cross(grass)) = plan("short_grass") else {
+            panic!("short grass is not planned as a cross");
+        };
+        assert_eq!(grass.layers[0].texture, "short_grass");
+        assert!(grass.layers[0].tinted);
+    }
+
+    #[test]
+    fn crops_use_the_texture_of_their_stage() {
+        let texture = |name: &str, age: usize| {
+            let state = state(&format!("minecraft:{}", name), &[("age", &age.to_string())]);
+            face_texture_name(name, &state, BlockFace::South)
+        };
+
+        let wart: Vec<_> = (0..4).map(|age| texture("nether_wart", age)).collect();
+        assert_eq!(
+            wart,
+            [
+                "nether_wart_stage0",
+                "nether_wart_stage1",
+                "nether_wart_stage1",
+                "nether_wart_stage2"
+            ]
+        );
+        for age in 0..4 {
+            assert_eq!(
+                texture("sweet_berry_bush", age),
+                format!("sweet_berry_bush_stage{}", age)
+            );
+        }
+        for age in 0..3 {
+            assert_eq!(
+                texture("torchflower_crop", age),
+                format!("torchflower_crop_stage{}", age)
+            );
+        }
+        assert_eq!(texture("carrots", 5), "carrots_stage2");
+        // Other blocks with an age keep their name
+        assert_eq!(texture("cactus", 3), "cactus");
+    }
+
+    #[test]
+    fn fluids_fill_their_block_under_the_same_fluid() {
+        let water = || state("minecraft:water", &[("level", "0")]);
+        let world = ColumnView::new(&[
+            (0, water()),
+            (1, water()),
+            (2, state("minecraft:seagrass", &[])),
+            (3, water()),
+            (5, state("minecraft:lava", &[("level", "0")])),
+            (6, state("minecraft:lava", &[("level", "0")])),
+            (7, water()),
+        ]);
+        let context = |y: isize| {
+            let block = world.block(&ColumnView::at(y)).unwrap();
+            let fluid = block.water.as_deref().unwrap_or(block);
+            fluid_context(&world, fluid, &ColumnView::at(y))
+        };
+        let covered = |depth| FluidContext {
+            covered: true,
+            depth,
+        };
+        let surface = |depth| FluidContext {
+            covered: false,
+            depth,
+        };
+
+        let depth = Some(ColumnView::DEPTH);
+        assert_eq!(context(0), covered(depth));
+        // Water under a waterlogged block, and the water inside it
+        assert_eq!(context(1), covered(depth));
+        assert_eq!(context(2), covered(depth));
+        // Nothing above
+        assert_eq!(context(3), surface(depth));
+        // Lava is not shaded by depth, and water does not cover it
+        assert_eq!(context(5), covered(None));
+        assert_eq!(context(6), surface(None));
+
+        // Other blocks have no fluid context
+        let seagrass = world.block(&ColumnView::at(2)).unwrap();
+        assert_eq!(
+            fluid_context(&world, seagrass, &ColumnView::at(2)),
+            FluidContext::default()
+        );
+    }
+}
@@ -1,15 +1,23 @@
+use crate::coords::block_face::BlockFace;
 use crate::utils::darken_image;
 use image::{RgbaImage, imageops};
 
 /// Transform a texture for the top face of an isometric block
 /// Rotates 45 degrees and scales Y by 0.5
-/// Output: 24x12 pixels
-pub fn transform_top(texture: &RgbaImage) -> RgbaImage {
-    // Resize to 17x17 for better sampling
-    let resized = imageops::resize(texture, 17, 17, imageops::FilterType::Triangle);
+/// Output: `size` by `size / 2` pixels
+pub fn transform_top(texture: &RgbaImage, size: u32) -> RgbaImage {
+    // Resize to the width of the diamond's sides for better sampling, 17x17
+    // for 24 pixel sprites
+    let resized_dim = (size as f64 * std::f64::consts::FRAC_1_SQRT_2).round() as u32;
+    let resized = imageops::resize(
+        texture,
+        resized_dim,
+        resized_dim,
+        imageops::FilterType::Triangle,
+    );
 
-    // Create output image (24x12 for top face)
-    let mut output = RgbaImage::new(crate::render::renderer::SPRITE_SIZE, 12);
+    // Create output image (24x12 for top face of a 24 pixel sprite)
+    let mut output = RgbaImage::new(size, size / 2);
 
     // The transformation matrix for isometric top view:
     // 1. Rotate 45 degrees
@@ -19,58 +27,83 @@ pub fn transform_top(texture: &RgbaImage) -> RgbaImage {
     // using inverse transformation
     let cos45 = std::f64::consts::FRAC_1_SQRT_2;
     let sin45 = std::f64::consts::FRAC_1_SQRT_2;
+    let half = size as f64 / 2.0;
+    let last = (resized_dim - 1) as f64;
 
-    for out_y in 0..12 {
-        for out_x in 0..crate::render::renderer::SPRITE_SIZE {
+    for out_y in 0..size / 2 {
+        for out_x in 0..size {
             // Transform output coordinates back to source coordinates
             // First, center the output coordinates
-            let cx = out_x as f64 - 12.0;
-            let cy = (out_y as f64 - 6.0) * 2.0; // Scale Y back up
+            let cx = out_x as f64 - half;
+            let cy = (out_y as f64 - half / 2.0) * 2.0; // Scale Y back up
+
+            // Only pixels whose centres are inside the diamond, so the tops
+            // of neighbouring blocks meet without overlapping, which would
+            // show as seams on translucent surfaces
+            if (cx + 0.5).abs() + (cy + 1.0).abs() >= half {
+                continue;
+            }
 
             // Inverse rotate by -45 degrees
             let src_x = cx * cos45 + cy * sin45;
             let src_y = -cx * sin45 + cy * cos45;
 
             // Translate to source image coordinates
-            let src_x = src_x + 8.5;
-            let src_y = src_y + 8.5;
-
-            // Sample from source image if in bounds
-            if src_x >= 0.0 && src_x < 17.0 && src_y >= 0.0 && src_y < 17.0 {
-                let sx = src_x as u32;
-                let sy = src_y as u32;
-                if sx < 17 && sy < 17 {
-                    let pixel = resized.get_pixel(sx, sy);
-                    output.put_pixel(out_x, out_y, *pixel);
-                }
-            }
+            let src_x = src_x + resized_dim as f64 / 2.0;
+            let src_y = src_y + resized_dim as f64 / 2.0;
+
+            // Sample from source image, at its edge for pixels of the
+            // diamond's rim that fall just outside it
+            let sx = src_x.clamp(0.0, last) as u32;
+            let sy = src_y.clamp(0.0, last) as u32;
+            let pixel = resized.get_pixel(sx, sy);
+            output.put_pixel(out_x, out_y, *pixel);
         }
     }
 
     output
 }
 
-pub fn transform_side(texture: &RgbaImage, side: BlockSpriteSide) -> RgbaImage {
-    const RESIZED_DIM: u32 = 12;
-    const SHEARED_HEIGHT: u32 = 18;
+/// Brightness of a face by the way it faces, the sides being in shade
+pub fn face_shade(face: BlockFace) -> f64 {
+    match face {
+        BlockFace::Top => 1.0,
+        BlockFace::South => 0.9,
+        BlockFace::East => 0.8,
+    }
+}
+
+/// Transform a texture for a side face of an isometric block of `size`
+/// pixels, shearing it by half a pixel down per pixel across
+/// Output: `size / 2` by `size * 3 / 4` pixels
+pub fn transform_side(texture: &RgbaImage, side: BlockSpriteSide, size: u32) -> RgbaImage {
+    let resized_dim = size / 2;
+    let sheared_height = size * 3 / 4;
+
+    // The right side is sheared the other way by mirroring the left one, so
+    // mirror its texture first to keep it the right way round
+    let texture = match side {
+        BlockSpriteSide::SideLeft => texture.clone(),
+        BlockSpriteSide::SideRight => imageops::flip_horizontal(texture),
+    };
 
-    // Resize to 12x12
+    // Resize to half the sprite, 12x12 for 24 pixel sprites
     let resized = imageops::resize(
-        texture,
-        RESIZED_DIM,
-        RESIZED_DIM,
+        &texture,
+        resized_dim,
+        resized_dim,
         imageops::FilterType::Triangle,
     );
 
     // Create output image (12x18 for side face after shear)
-    let mut output = RgbaImage::new(RESIZED_DIM, SHEARED_HEIGHT);
+    let mut output = RgbaImage::new(resized_dim, sheared_height);
 
     let shear_factor = 0.5;
 
     // Shear transformation: y_new = y + 0.5 * x
     // Inverse: y_src = y_out - 0.5 * x_out
-    for out_y in 0..SHEARED_HEIGHT {
-        for out_x in 0..RESIZED_DIM {
+    for out_y in 0..sheared_height {
+        for out_x in 0..resized_dim {
             // Apply inverse shear to find source coordinates
             let src_x = out_x as f64;
             let src_y = out_y as f64 - shear_factor * out_x as f64;
@@ -88,8 +121,8 @@ pub fn transform_side(texture: &RgbaImage, side: BlockSpriteSide) -> RgbaImage {
 
     // Darken the sides (left 0.9, right 0.8)
     let side_darken_factor = match side {
-        BlockSpriteSide::SideLeft => 0.9,
-        BlockSpriteSide::SideRight => 0.8,
+        BlockSpriteSide::SideLeft => face_shade(BlockFace::South),
+        BlockSpriteSide::SideRight => face_shade(BlockFace::East),
     };
     let darkened_img = darken_image(&output, side_darken_factor);
 
@@ -103,3 +136,27 @@ pub enum BlockSpriteSide {
     SideLeft,
     SideRight,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn faces_scale_with_the_sprite() {
+        let texture = RgbaImage::from_pixel(16, 16, Rgba([200, 200, 200, 255]));
+        for size in [24, 48] {
+            let top = transform_top(&texture, size);
+            assert_eq!(top.dimensions(), (size, size / 2));
+            // The diamond reaches the middle of every edge, not the corners
+            assert!(top.get_pixel(size / 2, 0)[3] > 0);
+            assert!(top.get_pixel(1, size / 4)[3] > 0);
+            assert_eq!(top.get_pixel(0, 0)[3], 0);
+
+            let side = transform_side(&texture, BlockSpriteSide::SideLeft, size);
+            assert_eq!(side.dimensions(), (size / 2, size * 3 / 4));
+            assert!(side.get_pixel(0, 0)[3] > 0);
+            assert_eq!(side.get_pixel(size / 2 - 1, 0)[3], 0);
+        }
+    }
+}
@@ -1,6 +1,10 @@
+use core::fmt;
+use std::collections::BTreeMap;
+
 use fastnbt::{ByteArray, LongArray};
 use serde::Deserialize;
 
+use crate::block_registry::{BlockId, BlockRegistry};
 use crate::coords::chunk_local_block_coord::ChunkLocalBlockCoord;
 
 #[derive(Deserialize, Debug)]
@@ -9,6 +13,8 @@ pub struct Section {
     pub y: i8,
     #[serde(rename = "block_states")]
     pub block_states: Option<BlockStates>,
+    #[serde(rename = "biomes")]
+    pub biomes: Option<Biomes>,
     pub block_light: Option<ByteArray>,
     pub sky_light: Option<ByteArray>,
 }
@@ -19,89 +25,275 @@ pub struct BlockStates {
     pub data: Option<LongArray>,
     #[serde(default)]
     pub unpacked_data: Option<Vec<u16>>,
+    /// Registry ID of each palette entry, see `intern_palette`
+    #[serde(skip)]
+    pub palette_ids: Vec<BlockId>,
 }
 
 impl BlockStates {
-    fn unpack_blockstates(data: &LongArray) -> Vec<u16> {
-        const BLOCK_COUNT: usize = 4096; // 16 * 16 * 16
+    const BLOCK_COUNT: usize = 4096; // 16 * 16 * 16
+
+    fn palette_index(&self, index: usize) -> Option<usize> {
+        // Legacy numeric sections come unpacked without packed data
+        if let Some(unpacked) = &self.unpacked_data {
+            return Some(unpacked[index] as usize);
+        }
+        match &self.data {
+            None => Some(0),
+            // Should be ensured unpacked before calling
+            Some(_) => None,
+        }
+    }
 
-        // Calculate bits per value (minimum 4 bits)
-        let bits_per_value = std::cmp::max(4, (data.len() * 64) / BLOCK_COUNT);
+    fn block_id_at(&self, index: usize) -> Option<BlockId> {
+        self.palette_ids.get(self.palette_index(index)?).copied()
+    }
 
-        // How many values fit in one 64-bit long
-        let values_per_long = 64 / bits_per_value;
+    pub fn intern_palette(&mut self, registry: &mut BlockRegistry) {
+        self.palette_ids = self
+            .palette
+            .iter()
+            .map(|entry| registry.intern(entry))
+            .collect();
+    }
+
+    pub fn ensure_unpacked(&mut self, packing: BitPacking) -> Result<(), PackingError> {
+        if let Some(data) = &self.data
+            && self.unpacked_data.is_none()
+        {
+            // At least 4 bits, more if the palette needs them
+            let bits_per_value = bits_for_palette(self.palette.len()).max(4);
+            self.unpacked_data = Some(unpack_indices(
+                data,
+                packing,
+                bits_per_value,
+                Self::BLOCK_COUNT,
+                self.palette.len(),
+            )?);
+        }
+        Ok(())
+    }
+}
 
-        // Create bitmask for extracting values
-        let mask = (1u64 << bits_per_value) - 1;
+/// Biomes of a section, stored per 4x4x4 cell
+#[derive(Deserialize, Debug)]
+pub struct Biomes {
+    pub palette: Vec<String>,
+    pub data: Option<LongArray>,
+    #[serde(default)]
+    pub unpacked_data: Option<Vec<u16>>,
+}
 
-        let mut result = Vec::with_capacity(BLOCK_COUNT);
+impl Biomes {
+    const CELL_COUNT: usize = 64; // 4 * 4 * 4
 
-        // Extract values from packed longs
-        'outer: for &long_value in data.iter() {
-            for j in 0..values_per_long {
-                if result.len() >= BLOCK_COUNT {
-                    break 'outer;
-                }
+    fn biome_at(&self, index: usize) -> Option<&str> {
+        let palette_index = match &self.unpacked_data {
+            Some(unpacked) => unpacked[index] as usize,
+            None => 0,
+        };
+        self.palette.get(palette_index).map(String::as_str)
+    }
 
-                // Extract the value at position j from this long
-                let value = (((long_value as u64) >> (j * bits_per_value)) & mask) as u16;
-                result.push(value);
-            }
+    /// Biomes only exist in sections since 1.18, so are always aligned
+    pub fn ensure_unpacked(&mut self) -> Result<(), PackingError> {
+        if let Some(data) = &self.data
+            && self.unpacked_data.is_none()
+        {
+            self.unpacked_data = Some(unpack_indices(
+                data,
+                BitPacking::Aligned,
+                bits_for_palette(self.palette.len()),
+                Self::CELL_COUNT,
+                self.palette.len(),
+            )?);
         }
+        Ok(())
+    }
+}
+
+/// First data version (20w17a) whose packed values do not span longs
+pub const ALIGNED_PACKING_VERSION: i32 = 2527;
+
+/// How palette indices are packed into a `LongArray`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitPacking {
+    /// Values never straddle two longs; leftover high bits of each long
+    /// are padding. Used since 1.16
+    Aligned,
+    /// Values are packed back to back, continuing into the next long.
+    /// Used from 1.13 to 1.15
+    Spanning,
+}
 
-        result
+impl BitPacking {
+    pub fn for_version(data_version: Option<i32>) -> Self {
+        match data_version {
+            Some(version) if version < ALIGNED_PACKING_VERSION => BitPacking::Spanning,
+            _ => BitPacking::Aligned,
+        }
     }
 
-    fn block_at(&self, index: usize) -> Option<&PaletteEntry> {
-        match &self.data {
-            None => self.palette.first(),
-            Some(_) => {
-                if let Some(unpacked) = &self.unpacked_data {
-                    let palette_index = unpacked[index];
-                    self.palette.get(palette_index as usize)
-                } else {
-                    // Should be ensured unpacked before calling
-                    None
-                }
-            }
+    /// Number of longs holding `count` values of `bits_per_value` bits
+    pub fn long_count(self, bits_per_value: usize, count: usize) -> usize {
+        match self {
+            BitPacking::Aligned => count.div_ceil(64 / bits_per_value),
+            BitPacking::Spanning => (count * bits_per_value).div_ceil(64),
         }
     }
+}
 
-    pub fn ensure_unpacked(&mut self) {
-        if let Some(data) = &self.data {
-            if self.unpacked_data.is_none() {
-                self.unpacked_data = Some(Self::unpack_blockstates(data));
+/// Packed palette indices that cannot be unpacked
+#[derive(Debug)]
+pub enum PackingError {
+    /// Fewer longs than the palette size and layout call for
+    TooShort { len: usize, expected: usize },
+    /// An unpacked value points past the end of the palette
+    IndexOutOfRange { index: u16, palette_len: usize },
+}
+
+impl fmt::Display for PackingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackingError::TooShort { len, expected } => {
+                write!(f, "packed data has {} longs, expected {}", len, expected)
             }
+            PackingError::IndexOutOfRange { index, palette_len } => write!(
+                f,
+                "palette index {} is out of range for {} entries",
+                index, palette_len
+            ),
         }
     }
 }
 
-#[derive(Deserialize, Debug)]
+/// Just enough bits to index a palette of `len` entries, at least 1
+fn bits_for_palette(len: usize) -> usize {
+    (usize::BITS - (len.max(2) - 1).leading_zeros()) as usize
+}
+
+/// Unpack `count` palette indices of `bits_per_value` bits each, checking
+/// that every index is within the palette
+fn unpack_indices(
+    data: &LongArray,
+    packing: BitPacking,
+    bits_per_value: usize,
+    count: usize,
+    palette_len: usize,
+) -> Result<Vec<u16>, PackingError> {
+    let indices = unpack_values(data, packing, bits_per_value, count)?;
+    match indices.iter().find(|&&index| index as usize >= palette_len) {
+        Some(&index) => Err(PackingError::IndexOutOfRange { index, palette_len }),
+        None => Ok(indices),
+    }
+}
+
+/// Unpack `count` values of `bits_per_value` bits each
+pub fn unpack_values(
+    data: &LongArray,
+    packing: BitPacking,
+    bits_per_value: usize,
+    count: usize,
+) -> Result<Vec<u16>, PackingError> {
+    let expected = packing.long_count(bits_per_value, count);
+    if data.len() < expected {
+        return Err(PackingError::TooShort {
+            len: data.len(),
+            expected,
+        });
+    }
+
+    // How many values fit in one 64-bit long, for the aligned layout
+    let values_per_long = 64 / bits_per_value;
+
+    // Create bitmask for extracting values
+    let mask = (1u64 << bits_per_value) - 1;
+
+    let result = (0..count)
+        .map(|i| {
+            let value = match packing {
+                BitPacking::Aligned => {
+                    let long_value = data[i / values_per_long] as u64;
+                    long_value >> ((i % values_per_long) * bits_per_value)
+                }
+                BitPacking::Spanning => {
+                    let bit = i * bits_per_value;
+                    let (long_index, offset) = (bit / 64, bit % 64);
+                    let mut value = data[long_index] as u64 >> offset;
+                    // Take the high bits from the next long if the value continues there
+                    if offset + bits_per_value > 64 {
+                        value |= (data[long_index + 1] as u64) << (64 - offset);
+                    }
+                    value
+                }
+            };
+            (value & mask) as u16
+        })
+        .collect();
+
+    Ok(result)
+}
+
+/// A block state: the block name and its properties, like `axis=y` for logs
+#[derive(Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
 #[serde(rename_all = "PascalCase")]
 pub struct PaletteEntry {
     pub name: String,
-    // pub properties: Option<HashMap<String, String>>,
+    /// Sorted, so equal states compare and hash equally
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+impl PaletteEntry {
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+}
+
+/// Formats the state like the game does, e.g. `minecraft:oak_log[axis=x]`
+impl fmt::Display for PaletteEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.properties.is_empty() {
+            return Ok(());
+        }
+        write!(f, "[")?;
+        for (i, (key, value)) in self.properties.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}={}", key, value)?;
+        }
+        write!(f, "]")
+    }
 }
 
 impl Section {
-    pub fn ensure_unpacked(&mut self) {
+    pub fn ensure_unpacked(&mut self, packing: BitPacking) -> Result<(), PackingError> {
         if let Some(states) = &mut self.block_states {
-            states.ensure_unpacked();
+            states.ensure_unpacked(packing)?;
+        }
+        if let Some(biomes) = &mut self.biomes {
+            biomes.ensure_unpacked()?;
         }
+        Ok(())
     }
 
-    pub fn block_at(&self, coords: ChunkLocalBlockCoord) -> Option<&PaletteEntry> {
-        match &self.block_states {
-            None => None,
-            Some(states) => states.block_at(coords.index()),
-        }
+    /// ID of the block at `coords`, once the palette is interned
+    pub fn block_id_at(&self, coords: ChunkLocalBlockCoord) -> Option<BlockId> {
+        self.block_states.as_ref()?.block_id_at(coords.index())
+    }
+
+    pub fn biome_at(&self, coords: ChunkLocalBlockCoord) -> Option<&str> {
+        let index = (coords.ly / 4) * 16 + (coords.lz / 4) * 4 + coords.lx / 4;
+        self.biomes.as_ref()?.biome_at(index)
     }
 
     fn light_index(coords: ChunkLocalBlockCoord, light_data: &ByteArray) -> u8 {
         let index = coords.index();
         let byte = light_data[index / 2];
-        let byte = byte.clone() as u8;
-        let shift: i8 = if index % 2 == 0 { 0 } else { 4 };
+        let byte = byte as u8;
+        let shift: i8 = if index.is_multiple_of(2) { 0 } else { 4 };
         (byte >> shift) & 0x0F
     }
 
@@ -117,3 +309,110 @@ impl Section {
             .map(|light| Self::light_index(coords, light))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastnbt::nbt;
+
+    #[test]
+    fn palette_entries_keep_their_properties() {
+        let nbt = nbt!({
+            "Y": 0i8,
+            "block_states": {
+                "palette": [
+                    {"Name": "minecraft:stone"},
+                    {"Name": "minecraft:oak_log", "Properties": {"axis": "x"}},
+                ],
+            },
+        });
+        let section: Section = fastnbt::from_value(&nbt).unwrap();
+        let palette = &section.block_states.unwrap().palette;
+
+        assert!(palette[0].properties.is_empty());
+        assert_eq!(palette[1].property("axis"), Some("x"));
+        assert_eq!(palette[1].to_string(), "minecraft:oak_log[axis=x]");
+    }
+
+    #[test]
+    fn biomes_unpack_per_cell() {
+        // Three biomes need two bits per cell; cell 1 is desert, cell 63 is swamp
+        let nbt = nbt!({
+            "Y": 0i8,
+            "biomes": {
+                "palette": ["minecraft:plains", "minecraft:desert", "minecraft:swamp"],
+                "data": fastnbt::LongArray::new(vec![0b0100, 2 << 62]),
+            },
+        });
+        let mut section: Section = fastnbt::from_value(&nbt).unwrap();
+        section.ensure_unpacked(BitPacking::Aligned).unwrap();
+
+        let at = |lx, ly, lz| section.biome_at(ChunkLocalBlockCoord { lx, ly, lz });
+        assert_eq!(at(0, 0, 0), Some("minecraft:plains"));
+        assert_eq!(at(5, 3, 2), Some("minecraft:desert"));
+        assert_eq!(at(15, 15, 15), Some("minecraft:swamp"));
+    }
+
+    /// 17 palette entries, so 5 bits per block
+    fn five_bit_states(data: Vec<i64>) -> BlockStates {
+        BlockStates {
+            palette: (0..17)
+                .map(|i| PaletteEntry {
+                    name: format!("minecraft:block_{}", i),
+                    properties: BTreeMap::new(),
+                })
+                .collect(),
+            data: Some(LongArray::new(data)),
+            unpacked_data: None,
+            palette_ids: Vec::new(),
+        }
+    }
+
+    fn name_at(states: &BlockStates, index: usize) -> &str {
+        &states.palette[states.palette_index(index).unwrap()].name
+    }
+
+    #[test]
+    fn block_states_unpack_both_layouts() {
+        // Spanning: block 12 takes the top 4 bits of the first long and
+        // the lowest bit of the second
+        let mut data = vec![0i64; 320];
+        data[0] = 0b01111 << 55;
+        data[1] = 1;
+        let mut spanning = five_bit_states(data);
+        spanning.ensure_unpacked(BitPacking::Spanning).unwrap();
+        assert_eq!(name_at(&spanning, 11), "minecraft:block_15");
+        assert_eq!(name_at(&spanning, 12), "minecraft:block_16");
+
+        // Aligned: 12 blocks per long and 4 bits of padding, so block 12
+        // starts the second long
+        let mut data = vec![0i64; 342];
+        data[0] = 0b1111 << 60;
+        data[1] = 16;
+        let mut aligned = five_bit_states(data);
+        aligned.ensure_unpacked(BitPacking::Aligned).unwrap();
+        assert_eq!(name_at(&aligned, 11), "minecraft:block_0");
+        assert_eq!(name_at(&aligned, 12), "minecraft:block_16");
+    }
+
+    #[test]
+    fn block_states_reject_bad_packing() {
+        let mut short = five_bit_states(vec![0; 319]);
+        assert!(matches!(
+            short.ensure_unpacked(BitPacking::Spanning),
+            Err(PackingError::TooShort {
+                len: 319,
+                expected: 320
+            })
+        ));
+
+        let mut out_of_range = five_bit_states(vec![17; 342]);
+        assert!(matches!(
+            out_of_range.ensure_unpacked(BitPacking::Aligned),
+            Err(PackingError::IndexOutOfRange {
+                index: 17,
+                palette_len: 17
+            })
+        ));
+    }
+}
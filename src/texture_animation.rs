@@ -0,0 +1,168 @@
+use std::str::FromStr;
+
+use image::{Rgba, RgbaImage, imageops::crop_imm};
+use serde::Deserialize;
+
+/// Which frame of animated textures to draw
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFrame {
+    /// A frame of the animation, counted from 0 and wrapping around
+    Index(usize),
+    /// Every frame blended, each weighted by how long it shows
+    Average,
+}
+
+impl FromStr for AnimationFrame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "average" => Ok(AnimationFrame::Average),
+            _ => s
+                .parse()
+                .map(AnimationFrame::Index)
+                .map_err(|_| format!("`{}` is neither a frame number nor `average`", s)),
+        }
+    }
+}
+
+/// The `.png.mcmeta` file next to a texture
+#[derive(Deserialize, Debug)]
+pub struct TextureMeta {
+    pub animation: Option<Animation>,
+}
+
+/// How a texture holding several frames is animated. Frames are laid out
+/// in rows, left to right
+#[derive(Deserialize, Debug, Default)]
+pub struct Animation {
+    /// Ticks each frame shows unless it says otherwise
+    frametime: Option<u32>,
+    /// Frames in the order they show, every frame in order when unset
+    frames: Option<Vec<AnimationStep>>,
+    /// Size of a frame, square and as wide as the texture when unset
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+/// A frame of an animation, with its own time or the animation's
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum AnimationStep {
+    Index(u32),
+    Timed { index: u32, time: Option<u32> },
+}
+
+impl Animation {
+    /// The frame of `texture` to draw
+    pub fn frame(&self, texture: &RgbaImage, choice: AnimationFrame) -> RgbaImage {
+        let side = texture.width().min(texture.height());
+        let width = self.width.unwrap_or(side).clamp(1, texture.width());
+        let height = self.height.unwrap_or(side).clamp(1, texture.height());
+        let columns = texture.width() / width;
+        let count = columns * (texture.height() / height);
+
+        // Frames show for at least a tick, so the times never sum to zero
+        let default_time = self.frametime.unwrap_or(1).max(1);
+        let steps: Vec<(u32, u32)> = match &self.frames {
+            Some(frames) => frames
+                .iter()
+                .map(|step| match *step {
+                    AnimationStep::Index(index) => (index, default_time),
+                    AnimationStep::Timed { index, time } => {
+                        (index, time.unwrap_or(default_time).max(1))
+                    }
+                })
+                .filter(|&(index, _)| index < count)
+                .collect(),
+            None => (0..count).map(|index| (index, default_time)).collect(),
+        };
+        let crop_frame = |index: u32| {
+            let (x, y) = (index % columns * width, index / columns * height);
+            crop_imm(texture, x, y, width, height).to_image()
+        };
+
+        match choice {
+            _ if steps.is_empty() => crop_frame(0),
+            AnimationFrame::Index(position) => crop_frame(steps[position % steps.len()].0),
+            AnimationFrame::Average => {
+                let total: u32 = steps.iter().map(|&(_, time)| time).sum();
+                let mut sums = vec![[0u64; 4]; (width * height) as usize];
+                for &(index, time) in &steps {
+                    for (sum, pixel) in sums.iter_mut().zip(crop_frame(index).pixels()) {
+                        for channel in 0..4 {
+                            sum[channel] += pixel[channel] as u64 * time as u64;
+                        }
+                    }
+                }
+                RgbaImage::from_fn(width, height, |x, y| {
+                    let sum = sums[(y * width + x) as usize];
+                    Rgba(sum.map(|channel| (channel / total as u64) as u8))
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A strip of `frames` square frames, each filled with its index
+    fn strip(frames: u32) -> RgbaImage {
+        RgbaImage::from_fn(2, 2 * frames, |_, y| Rgba([(y / 2) as u8 * 10, 0, 0, 255]))
+    }
+
+    #[test]
+    fn frames_are_picked_from_the_strip() {
+        let texture = strip(4);
+        let animation = Animation::default();
+        let frame = animation.frame(&texture, AnimationFrame::Index(2));
+        assert_eq!(frame.dimensions(), (2, 2));
+        assert_eq!(frame.get_pixel(1, 1)[0], 20);
+        // Positions past the end wrap around
+        let frame = animation.frame(&texture, AnimationFrame::Index(5));
+        assert_eq!(frame.get_pixel(0, 0)[0], 10);
+
+        let average = animation.frame(&texture, AnimationFrame::Average);
+        assert_eq!(average.get_pixel(0, 0)[0], 15);
+    }
+
+    #[test]
+    fn listed_frames_keep_their_order_and_times() {
+        let meta: TextureMeta = serde_json::from_str(
+            r#"{ "animation": { "frametime": 2, "frames": [3, { "index": 1, "time": 6 }, 9] } }"#,
+        )
+        .unwrap();
+        let animation = meta.animation.unwrap();
+        let texture = strip(4);
+
+        // The frame beyond the strip is left out
+        assert_eq!(
+            animation
+                .frame(&texture, AnimationFrame::Index(0))
+                .get_pixel(0, 0)[0],
+            30
+        );
+        assert_eq!(
+            animation
+                .frame(&texture, AnimationFrame::Index(2))
+                .get_pixel(0, 0)[0],
+            30
+        );
+        // Frame 3 shows for 2 ticks and frame 1 for 6
+        let average = animation.frame(&texture, AnimationFrame::Average);
+        assert_eq!(average.get_pixel(0, 0)[0], 15);
+    }
+
+    #[test]
+    fn frames_without_time_show_for_a_tick() {
+        let meta: TextureMeta = serde_json::from_str(
+            r#"{ "animation": { "frametime": 0, "frames": [{ "index": 0, "time": 0 }, { "index": 2, "time": 0 }] } }"#,
+        )
+        .unwrap();
+        let animation = meta.animation.unwrap();
+        let average = animation.frame(&strip(4), AnimationFrame::Average);
+        assert_eq!(average.get_pixel(0, 0)[0], 10);
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::coords::block_face::BlockFace;
+use crate::data_file::{DataFileError, NamePattern, read_json};
+
+/// Mapping shipped with the renderer, see `TextureMap`
+const BUNDLED_TEXTURES: &str = include_str!("../data/textures.json");
+
+/// Aliases followed before giving up, in case a file maps blocks in a circle
+const MAX_ALIASES: usize = 8;
+
+/// One texture name or several to try in order
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Candidates {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Candidates {
+    fn names(&self) -> &[String] {
+        match self {
+            Candidates::One(name) => std::slice::from_ref(name),
+            Candidates::Many(names) => names,
+        }
+    }
+}
+
+/// Textures of a block. `{name}` in a texture name stands for the block
+/// name and `{base}` for the part of it a pattern did not match
+#[derive(Deserialize, Debug, Default)]
+#[serde(from = "EntryForm")]
+struct TextureEntry {
+    /// Textured like another block
+    alias: Option<String>,
+    /// Every face
+    texture: Option<Candidates>,
+    top: Option<Candidates>,
+    /// The east and south faces
+    side: Option<Candidates>,
+}
+
+impl TextureEntry {
+    fn face(&self, face: &BlockFace) -> Option<&Candidates> {
+        let specific = match face {
+            BlockFace::Top => &self.top,
+            BlockFace::East | BlockFace::South => &self.side,
+        };
+        specific.as_ref().or(self.texture.as_ref())
+    }
+}
+
+/// An entry is either a single texture name for every face or an object
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EntryForm {
+    Texture(String),
+    Faces {
+        alias: Option<String>,
+        texture: Option<Candidates>,
+        top: Option<Candidates>,
+        side: Option<Candidates>,
+    },
+}
+
+impl From<EntryForm> for TextureEntry {
+    fn from(form: EntryForm) -> Self {
+        match form {
+            EntryForm::Texture(name) => TextureEntry {
+                texture: Some(Candidates::One(name)),
+                ..Default::default()
+            },
+            EntryForm::Faces {
+                alias,
+                texture,
+                top,
+                side,
+            } => TextureEntry {
+                alias,
+                texture,
+                top,
+                side,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct PatternEntry {
+    #[serde(flatten)]
+    pattern: NamePattern,
+    #[serde(flatten)]
+    entry: TextureEntry,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct TextureMapFile {
+    #[serde(default)]
+    blocks: HashMap<String, TextureEntry>,
+    #[serde(default)]
+    patterns: Vec<PatternEntry>,
+}
+
+impl TextureMapFile {
+    /// The block's own entry, or else the first pattern matching it, with
+    /// the `{base}` of the name
+    fn entry_for<'a>(&'a self, name: &'a str) -> Option<(&'a TextureEntry, &'a str)> {
+        if let Some(entry) = self.blocks.get(name) {
+            return Some((entry, name));
+        }
+        self.patterns.iter().find_map(|pattern| {
+            let base = pattern.pattern.strip(name)?;
+            Some((&pattern.entry, base))
+        })
+    }
+}
+
+/// Which texture files a block's faces use. Read from the bundled
+/// `data/textures.json`, with an optional user file in the same format
+/// whose entries take precedence. Names not mapped by any entry are used
+/// as texture names. Textures of other namespaces are written like
+/// `mymod:ruby_ore`
+#[derive(Debug)]
+pub struct TextureMap {
+    /// Highest precedence first
+    files: Vec<TextureMapFile>,
+}
+
+impl TextureMap {
+    pub fn bundled() -> Self {
+        let bundled = serde_json::from_str(BUNDLED_TEXTURES).expect("bundled texture map");
+        Self {
+            files: vec![bundled],
+        }
+    }
+
+    /// The bundled mapping with the user's entries on top
+    pub fn load(overrides: Option<&Path>) -> Result<Self, DataFileError> {
+        let mut texture_map = Self::bundled();
+        if let Some(path) = overrides {
+            texture_map.files.insert(0, read_json(path)?);
+        }
+        Ok(texture_map)
+    }
+
+    /// Texture names for a face of a block, best first. The first one that
+    /// exists in the assets should be used
+    pub fn candidates(&self, name: &str, face: &BlockFace) -> Vec<String> {
+        let mut name = name.to_string();
+        'aliases: for _ in 0..MAX_ALIASES {
+            for file in &self.files {
+                let Some((entry, base)) = file.entry_for(&name) else {
+                    continue;
+                };
+                let expand =
+                    |template: &str| template.replace("{name}", &name).replace("{base}", base);
+
+                if let Some(candidates) = entry.face(face)
+                    && !candidates.names().is_empty()
+                {
+                    return candidates.names().iter().map(|c| expand(c)).collect();
+                }
+                if let Some(alias) = &entry.alias {
+                    name = expand(alias);
+                    continue 'aliases;
+                }
+            }
+            break;
+        }
+        vec![name]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_map_resolves_faces_and_patterns() {
+        let map = TextureMap::bundled();
+        let top = |name| map.candidates(name, &BlockFace::Top);
+        let side = |name| map.candidates(name, &BlockFace::East);
+
+        assert_eq!(top("snow_block"), ["snow"]);
+        assert_eq!(top("hopper"), ["hopper_top"]);
+        assert_eq!(side("hopper"), ["hopper_outside"]);
+        assert_eq!(side("stone"), ["stone", "stone_side"]);
+        assert_eq!(top("barrel"), ["barrel", "barrel_top"]);
+        assert_eq!(
+            side("stone_brick_stairs")[..2],
+            ["stone_brick_planks", "stone_bricks"]
+        );
+        assert_eq!(side("oak_fence")[0], "oak_planks");
+        // Waxed copper looks like the unwaxed block
+        assert_eq!(side("waxed_cut_copper_slab")[2], "cut_copper");
+        assert_eq!(side("red_stained_glass_pane"), ["red_stained_glass"]);
+    }
+
+    #[test]
+    fn user_entries_take_precedence() {
+        let user: TextureMapFile = serde_json::from_str(
+            r#"{
+                "blocks": { "snow_block": { "top": "powder_snow" } },
+                "patterns": [{ "prefix": "mymod:", "suffix": "_ore", "texture": "mymod:{base}" }]
+            }"#,
+        )
+        .unwrap();
+        let mut map = TextureMap::bundled();
+        map.files.insert(0, user);
+
+        assert_eq!(
+            map.candidates("snow_block", &BlockFace::Top),
+            ["powder_snow"]
+        );
+        // Faces the user does not map come from the bundled file
+        assert_eq!(map.candidates("snow_block", &BlockFace::South), ["snow"]);
+        assert_eq!(
+            map.candidates("mymod:ruby_ore", &BlockFace::East),
+            ["mymod:ruby"]
+        );
+    }
+}
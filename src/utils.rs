@@ -14,5 +14,13 @@ pub fn tint_image(img: &RgbaImage, tint: [f64; 3]) -> RgbaImage {
 
 /// Darken an image by a factor (0.0 = black, 1.0 = original)
 pub fn darken_image(img: &RgbaImage, factor: f64) -> RgbaImage {
-    return tint_image(img, [factor, factor, factor]);
+    tint_image(img, [factor, factor, factor])
+}
+
+/// Make every pixel fully opaque or fully clear, for textures with holes
+/// whose edges resampling has blurred
+pub fn cut_out(img: &mut RgbaImage) {
+    for pixel in img.pixels_mut() {
+        pixel[3] = if pixel[3] < 128 { 0 } else { 255 };
+    }
 }
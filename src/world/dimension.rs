@@ -0,0 +1,178 @@
+use core::fmt;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    coords::{chunk_bounds::ChunkBounds, region_coord::RegionCoord},
+    region::read_present_chunks,
+    world::level::WorldBorder,
+};
+
+/// Highest Y rendered in the Nether by default, just below the bedrock roof
+/// that starts at Y 123
+pub const NETHER_CEILING_Y: isize = 122;
+
+/// Light level standing in for sky light in the Nether, which has none.
+/// Faces are lit by the brighter of sky and block light, so without it
+/// everything away from lava, fire and glowstone would be drawn black. Not
+/// a game value; half brightness keeps the Nether dim but readable
+pub const NETHER_AMBIENT_LIGHT: u8 = 7;
+
+/// Light level standing in for sky light in the End, which has none
+/// either. Brighter than the Nether, as the game draws the End evenly lit;
+/// not a game value either
+pub const END_AMBIENT_LIGHT: u8 = 10;
+
+/// How sky light is looked up in a dimension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkyLight {
+    /// Sky light is stored per section, and blocks above the highest section
+    /// are open to the sky
+    Stored,
+    /// The dimension has no sky; every block gets the same ambient level
+    Ambient(u8),
+}
+
+/// Identifies a dimension of a world
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum DimensionId {
+    Overworld,
+    Nether,
+    End,
+    /// Datapack dimension, as a namespaced id like `mypack:mining`
+    Custom(String),
+}
+
+impl DimensionId {
+    /// Folder holding the dimension's data, relative to the world directory
+    pub fn relative_path(&self) -> PathBuf {
+        match self {
+            DimensionId::Overworld => PathBuf::new(),
+            DimensionId::Nether => PathBuf::from("DIM-1"),
+            DimensionId::End => PathBuf::from("DIM1"),
+            DimensionId::Custom(id) => {
+                let (namespace, name) = id.split_once(':').unwrap_or(("minecraft", id));
+                Path::new("dimensions").join(namespace).join(name)
+            }
+        }
+    }
+
+    /// Sky light handling, following the vanilla dimension types. Datapack
+    /// dimensions are assumed to have a sky
+    pub fn sky_light(&self) -> SkyLight {
+        match self {
+            DimensionId::Nether => SkyLight::Ambient(NETHER_AMBIENT_LIGHT),
+            DimensionId::End => SkyLight::Ambient(END_AMBIENT_LIGHT),
+            DimensionId::Overworld | DimensionId::Custom(_) => SkyLight::Stored,
+        }
+    }
+
+    /// Highest Y worth rendering, for dimensions with a ceiling that would
+    /// hide everything below it
+    pub fn ceiling_y(&self) -> Option<isize> {
+        match self {
+            DimensionId::Nether => Some(NETHER_CEILING_Y),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for DimensionId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overworld" | "minecraft:overworld" => Ok(DimensionId::Overworld),
+            "nether" | "the_nether" | "minecraft:the_nether" => Ok(DimensionId::Nether),
+            "end" | "the_end" | "minecraft:the_end" => Ok(DimensionId::End),
+            _ if s.is_empty() || s.matches(':').count() > 1 => {
+                Err(format!("`{}` is not a dimension id", s))
+            }
+            _ if s.contains(':') => Ok(DimensionId::Custom(s.to_string())),
+            _ => Ok(DimensionId::Custom(format!("minecraft:{}", s))),
+        }
+    }
+}
+
+impl fmt::Display for DimensionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DimensionId::Overworld => write!(f, "minecraft:overworld"),
+            DimensionId::Nether => write!(f, "minecraft:the_nether"),
+            DimensionId::End => write!(f, "minecraft:the_end"),
+            DimensionId::Custom(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+/// A dimension found on disk
+#[derive(Debug, Clone)]
+pub struct Dimension {
+    pub id: DimensionId,
+    /// Root folder of the dimension (containing `region/`)
+    pub path: PathBuf,
+    /// Border from the dimension's `data/world_border.dat` (1.21.9+)
+    pub border: Option<WorldBorder>,
+}
+
+impl Dimension {
+    pub fn region_dir(&self) -> PathBuf {
+        self.path.join("region")
+    }
+
+    /// Region files of the dimension, sorted by name
+    pub fn region_files(&self) -> io::Result<Vec<PathBuf>> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(self.region_dir())?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let name = path.file_name()?.to_str()?;
+                RegionCoord::from_file_name(name)?;
+                Some(path)
+            })
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Bounds of every chunk present in the dimension's region files, or
+    /// `None` if there are none. Only the location tables are read; regions
+    /// whose table is unreadable are left out.
+    pub fn chunk_extent(&self) -> io::Result<Option<ChunkBounds>> {
+        let mut extent: Option<ChunkBounds> = None;
+        for path in self.region_files()? {
+            let Ok(chunks) = read_present_chunks(&path) else {
+                continue;
+            };
+            for chunk in chunks {
+                match &mut extent {
+                    Some(extent) => extent.include(chunk),
+                    None => extent = Some(ChunkBounds::single(chunk)),
+                }
+            }
+        }
+        Ok(extent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimension_id_parses_aliases_and_datapack_ids() {
+        assert_eq!("nether".parse(), Ok(DimensionId::Nether));
+        assert_eq!("minecraft:the_end".parse(), Ok(DimensionId::End));
+        assert_eq!(
+            "mypack:mining".parse(),
+            Ok(DimensionId::Custom("mypack:mining".to_string()))
+        );
+        assert!("a:b:c".parse::<DimensionId>().is_err());
+        assert_eq!(
+            DimensionId::Custom("mypack:mining".to_string()).relative_path(),
+            Path::new("dimensions/mypack/mining")
+        );
+    }
+}
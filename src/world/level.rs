@@ -0,0 +1,117 @@
+use fastnbt::IntArray;
+use serde::Deserialize;
+
+use crate::coords::{chunk_bounds::ChunkBounds, world_block_coord::WorldBlockCoord};
+
+#[derive(Deserialize, Debug)]
+pub struct LevelDat {
+    #[serde(rename = "Data")]
+    pub data: LevelData,
+}
+
+/// The `Data` compound of `level.dat`
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct LevelData {
+    pub level_name: String,
+    /// Absent in worlds older than 1.9
+    pub data_version: Option<i32>,
+    /// Game time in ticks
+    #[serde(default)]
+    pub time: i64,
+    /// Time of day in ticks
+    pub day_time: Option<i64>,
+
+    // Before 1.21.9 the spawn is stored as three separate ints
+    spawn_x: Option<i32>,
+    spawn_y: Option<i32>,
+    spawn_z: Option<i32>,
+    #[serde(rename = "spawn")]
+    spawn_point: Option<SpawnPoint>,
+
+    // 1.16+ keeps the seed in WorldGenSettings, older versions at the top level
+    random_seed: Option<i64>,
+    world_gen_settings: Option<WorldGenSettings>,
+
+    // Moved to `data/world_border.dat` in 1.21.9
+    border_center_x: Option<f64>,
+    border_center_z: Option<f64>,
+    border_size: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SpawnPoint {
+    pos: IntArray,
+}
+
+#[derive(Deserialize, Debug)]
+struct WorldGenSettings {
+    seed: i64,
+}
+
+impl LevelData {
+    pub fn spawn(&self) -> Option<WorldBlockCoord> {
+        if let Some(spawn) = &self.spawn_point
+            && let [x, y, z] = spawn.pos[..]
+        {
+            return Some(WorldBlockCoord {
+                x: x as isize,
+                y: y as isize,
+                z: z as isize,
+            });
+        }
+        Some(WorldBlockCoord {
+            x: self.spawn_x? as isize,
+            y: self.spawn_y? as isize,
+            z: self.spawn_z? as isize,
+        })
+    }
+
+    pub fn seed(&self) -> Option<i64> {
+        self.world_gen_settings
+            .as_ref()
+            .map(|settings| settings.seed)
+            .or(self.random_seed)
+    }
+
+    /// The world border stored in `level.dat` by versions before 1.21.9
+    pub fn border(&self) -> Option<WorldBorder> {
+        Some(WorldBorder {
+            center_x: self.border_center_x?,
+            center_z: self.border_center_z?,
+            size: self.border_size?,
+        })
+    }
+}
+
+/// Square world border, centred on a block position
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct WorldBorder {
+    pub center_x: f64,
+    pub center_z: f64,
+    /// Length of each side, in blocks
+    pub size: f64,
+}
+
+impl WorldBorder {
+    /// Chunks at least partly inside the border
+    pub fn chunk_bounds(&self) -> ChunkBounds {
+        let half = self.size / 2.0;
+        let corner = |x: f64, z: f64| WorldBlockCoord {
+            x: x.floor() as isize,
+            y: 0,
+            z: z.floor() as isize,
+        };
+        ChunkBounds::from_blocks(
+            corner(self.center_x - half, self.center_z - half),
+            // The far edge is exclusive
+            corner(self.center_x + half - 1.0, self.center_z + half - 1.0),
+        )
+    }
+}
+
+/// Contents of `data/world_border.dat`
+#[derive(Deserialize, Debug)]
+pub struct WorldBorderDat {
+    pub data: WorldBorder,
+}
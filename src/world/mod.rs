@@ -0,0 +1,244 @@
+use core::fmt;
+use std::{
+    fs, io,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use flate2::bufread::GzDecoder;
+use serde::de::DeserializeOwned;
+
+use crate::world::{
+    dimension::{Dimension, DimensionId},
+    level::{LevelDat, LevelData, WorldBorder, WorldBorderDat},
+};
+
+pub mod dimension;
+pub mod level;
+
+#[derive(Debug)]
+pub enum WorldError {
+    /// The directory has no `level.dat`, so it is probably not a world
+    MissingLevelDat(PathBuf),
+    Io(PathBuf, io::Error),
+    Nbt(PathBuf, fastnbt::error::Error),
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::MissingLevelDat(path) => write!(
+                f,
+                "{} has no level.dat; is it a world directory?",
+                path.display()
+            ),
+            WorldError::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            WorldError::Nbt(path, err) => write!(f, "failed to parse {}: {}", path.display(), err),
+        }
+    }
+}
+
+/// A Minecraft world directory: its `level.dat` and the dimensions on disk
+pub struct World {
+    pub path: PathBuf,
+    pub level: LevelData,
+    pub dimensions: Vec<Dimension>,
+}
+
+impl World {
+    pub fn open(path: &Path) -> Result<World, WorldError> {
+        let level_path = path.join("level.dat");
+        if !level_path.is_file() {
+            return Err(WorldError::MissingLevelDat(path.to_path_buf()));
+        }
+        let level: LevelDat = read_gzip_nbt(&level_path)?;
+
+        Ok(World {
+            path: path.to_path_buf(),
+            level: level.data,
+            dimensions: Self::discover_dimensions(path),
+        })
+    }
+
+    /// Find every dimension of the world directory that has a region folder,
+    /// without needing `level.dat`
+    pub fn discover_dimensions(path: &Path) -> Vec<Dimension> {
+        let mut ids = vec![
+            DimensionId::Overworld,
+            DimensionId::Nether,
+            DimensionId::End,
+        ];
+
+        // Datapack dimensions live in dimensions/<namespace>/<name>
+        let mut custom = Vec::new();
+        for namespace in subdirectories(&path.join("dimensions")) {
+            for name in subdirectories(&namespace) {
+                if let (Some(namespace), Some(name)) = (file_name(&namespace), file_name(&name)) {
+                    custom.push(DimensionId::Custom(format!("{}:{}", namespace, name)));
+                }
+            }
+        }
+        custom.sort_by_key(|id| id.to_string());
+        ids.extend(custom);
+
+        ids.into_iter()
+            .map(|id| {
+                let root = path.join(id.relative_path());
+                let border = read_gzip_nbt::<WorldBorderDat>(&root.join("data/world_border.dat"))
+                    .ok()
+                    .map(|dat| dat.data);
+                Dimension {
+                    id,
+                    path: root,
+                    border,
+                }
+            })
+            .filter(|dimension| dimension.region_dir().is_dir())
+            .collect()
+    }
+
+    pub fn dimension(&self, id: &DimensionId) -> Option<&Dimension> {
+        self.dimensions.iter().find(|dimension| &dimension.id == id)
+    }
+
+    /// Border of a dimension, falling back to the one in `level.dat`
+    pub fn border(&self, dimension: &Dimension) -> Option<WorldBorder> {
+        dimension.border.or_else(|| self.level.border())
+    }
+}
+
+/// Read a gzip-compressed NBT file such as `level.dat`
+fn read_gzip_nbt<T: DeserializeOwned>(path: &Path) -> Result<T, WorldError> {
+    let compressed = fs::read(path).map_err(|err| WorldError::Io(path.to_path_buf(), err))?;
+    let mut data = Vec::new();
+    GzDecoder::new(&compressed[..])
+        .read_to_end(&mut data)
+        .map_err(|err| WorldError::Io(path.to_path_buf(), err))?;
+    fastnbt::from_bytes(&data).map_err(|err| WorldError::Nbt(path.to_path_buf(), err))
+}
+
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name()?.to_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::*;
+
+    fn write_gzip_nbt(path: &Path, value: &fastnbt::Value) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&fastnbt::to_bytes(value).unwrap())
+            .unwrap();
+        fs::write(path, encoder.finish().unwrap()).unwrap();
+    }
+
+    /// An empty directory of its own for a test
+    fn temp_world(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn sample_world_opens() {
+        let world = World::open(&Path::new(env!("CARGO_MANIFEST_DIR")).join("sample_map")).unwrap();
+
+        assert_eq!(world.level.level_name, "New World");
+        assert_eq!(world.level.data_version, Some(4671));
+        assert_eq!(world.level.seed(), Some(3821642298380268625));
+        let spawn = world.level.spawn().unwrap();
+        assert_eq!((spawn.x, spawn.y, spawn.z), (0, 82, 0));
+
+        // Only the overworld has regions
+        let ids: Vec<_> = world.dimensions.iter().map(|d| &d.id).collect();
+        assert_eq!(ids, [&DimensionId::Overworld]);
+        let border = world.border(&world.dimensions[0]).unwrap();
+        assert_eq!((border.center_x, border.center_z), (0.0, 0.0));
+        assert_eq!(border.size, 59999968.0);
+    }
+
+    #[test]
+    fn world_border_dat_takes_precedence() {
+        let path = temp_world("world-border");
+        write_gzip_nbt(
+            &path.join("level.dat"),
+            &fastnbt::nbt!({
+                "Data": {
+                    "LevelName": "Old",
+                    "SpawnX": 1, "SpawnY": 2, "SpawnZ": 3,
+                    "RandomSeed": 42i64,
+                    "BorderCenterX": 0.0, "BorderCenterZ": 0.0, "BorderSize": 1000.0,
+                },
+            }),
+        );
+        fs::create_dir_all(path.join("region")).unwrap();
+        fs::create_dir_all(path.join("DIM-1/region")).unwrap();
+        write_gzip_nbt(
+            &path.join("data/world_border.dat"),
+            &fastnbt::nbt!({
+                "data": { "center_x": 16.0, "center_z": -16.0, "size": 64.0 },
+            }),
+        );
+
+        let world = World::open(&path);
+        fs::remove_dir_all(&path).unwrap();
+        let world = world.unwrap();
+
+        assert_eq!(world.level.seed(), Some(42));
+        let spawn = world.level.spawn().unwrap();
+        assert_eq!((spawn.x, spawn.y, spawn.z), (1, 2, 3));
+        let overworld = world.dimension(&DimensionId::Overworld).unwrap();
+        assert_eq!(
+            world.border(overworld),
+            Some(WorldBorder {
+                center_x: 16.0,
+                center_z: -16.0,
+                size: 64.0,
+            })
+        );
+        // The Nether has no world_border.dat of its own
+        let nether = world.dimension(&DimensionId::Nether).unwrap();
+        assert_eq!(world.border(nether).unwrap().size, 1000.0);
+    }
+
+    #[test]
+    fn datapack_dimensions_are_discovered() {
+        let path = temp_world("world-dimensions");
+        fs::create_dir_all(path.join("region")).unwrap();
+        fs::create_dir_all(path.join("dimensions/mypack/mining/region")).unwrap();
+        fs::create_dir_all(path.join("dimensions/mypack/caves/region")).unwrap();
+        // Dimensions without regions are left out
+        fs::create_dir_all(path.join("dimensions/other/empty")).unwrap();
+
+        let dimensions = World::discover_dimensions(&path);
+        fs::remove_dir_all(&path).unwrap();
+
+        let ids: Vec<_> = dimensions.iter().map(|d| d.id.to_string()).collect();
+        assert_eq!(
+            ids,
+            ["minecraft:overworld", "mypack:caves", "mypack:mining"]
+        );
+        assert!(dimensions[2].path.ends_with("dimensions/mypack/mining"));
+        assert!(matches!(
+            World::open(&path),
+            Err(WorldError::MissingLevelDat(_))
+        ));
+    }
+}